@@ -1,4 +1,16 @@
-use yggdrasil_appearancelib::{load_all, load_database_only};
+use yggdrasil_appearancelib::{Direction, LoadedAnimation, load_all, load_database_only};
+
+/// Descreve a temporização de uma animação (`100ms`, `60-90ms` ou `static`)
+fn timing(animation: &LoadedAnimation) -> String {
+    if animation.is_static() {
+        return "static".to_string();
+    }
+
+    match animation.total_duration() {
+        (min, max) if min == max => format!("{}ms/cycle", min),
+        (min, max) => format!("{}-{}ms/cycle", min, max),
+    }
+}
 
 fn main() -> Result<(), Box<dyn std::error::Error>> {
     println!("=== Yggdrasil Appearance Loader Example ===\n");
@@ -15,42 +27,51 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
 
     // Lista todas as appearances
     println!("Appearances loaded:");
-    for appearance in database.all_appearances() {
+    for appearance in database.iter() {
         println!(
-            "  • ID {}: {} (size: {}px)",
-            appearance.id, appearance.name, appearance.size
+            "  • ID {}: {} (cell: {}x{}px)",
+            appearance.id, appearance.name, appearance.width, appearance.height
         );
 
-        for (anim_name, animation) in &appearance.animations {
+        for framegroup in appearance.all_framegroups() {
             println!(
-                "    └─ {}: {}x{}, {} frames, {} directions, {}ms",
-                anim_name,
-                animation.width,
-                animation.height,
-                animation.frames,
-                animation.directions,
-                if animation.duration > 0 {
-                    format!("{}", animation.duration)
-                } else {
-                    "static".to_string()
-                }
+                "    └─ {}: {} direction(s)",
+                framegroup.name,
+                framegroup.animations.len()
             );
+
+            for (direction, animation) in &framegroup.animations {
+                println!(
+                    "       └─ {:?}: {} frames, {}",
+                    direction,
+                    animation.sprite_ids.len(),
+                    timing(animation)
+                );
+            }
         }
     }
     println!();
 
     // Exemplo 2: Carregar sprite sob demanda
-    if let Some(appearance) = database.get_appearance(1) {
+    if let Some(appearance) = database.get(1) {
         println!("📦 Example 2: Loading sprite on-demand");
         println!("─────────────────────────────────────────────────────────");
-        println!("Loading sprites for appearance: {}", appearance.name);
-
-        for (anim_name, animation) in &appearance.animations {
-            let sprite = loader.load_sprite(animation.sprite_id)?;
+        println!("Loading first frames for appearance: {}", appearance.name);
+
+        for framegroup in appearance.all_framegroups() {
+            // Framegroups direcionais não têm animação padrão, então usa o sul
+            let Some(animation) = framegroup.get_animation_or_default(Some(Direction::South)) else {
+                continue;
+            };
+            let Some(&sprite_id) = animation.sprite_ids.first() else {
+                continue;
+            };
+
+            let sprite = loader.load_sprite(sprite_id)?;
             println!(
                 "  ✓ Loaded sprite {} ({}): {}x{}, {} bytes",
                 sprite.sprite_id,
-                anim_name,
+                framegroup.name,
                 sprite.width,
                 sprite.height,
                 sprite.pixels.len()
@@ -82,30 +103,37 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     );
     println!();
 
-    // Exemplo 4: Buscar appearance e animação específicas
+    // Exemplo 4: Buscar appearance e framegroup específicos
     println!("🔍 Example 4: Querying specific appearance");
     println!("─────────────────────────────────────────────────────────");
 
-    if let Some(warrior) = database2.get_appearance(1) {
+    if let Some(warrior) = database2.get(1) {
         println!("Found appearance: {}", warrior.name);
 
-        if let Some(idle_anim) = warrior.get_animation("idle") {
-            println!("  Animation 'idle':");
-            println!("    • Sprite ID: {}", idle_anim.sprite_id);
-            println!("    • Dimensions: {}x{}", idle_anim.width, idle_anim.height);
-            println!("    • Frames: {}", idle_anim.frames);
-            println!("    • Directions: {}", idle_anim.directions);
-
-            if let Some(sprite) = loader2.get_cached_sprite(idle_anim.sprite_id) {
+        if let Some(idle) = warrior.get_framegroup("idle")
+            && let Some(animation) = idle.get_animation_or_default(None)
+        {
+            println!("  Framegroup 'idle':");
+            println!("    • Sprite IDs: {:?}", animation.sprite_ids);
+            println!("    • Cell: {}x{}", warrior.width, warrior.height);
+            println!("    • Frames: {}", animation.sprite_ids.len());
+            println!("    • Directions: {}", idle.animations.len());
+            println!("    • Timing: {}", timing(animation));
+
+            if let Some(sprite) = animation
+                .sprite_ids
+                .first()
+                .and_then(|&sprite_id| loader2.get_cached_sprite(sprite_id))
+            {
                 println!("    • Pixel data: {} RGBA bytes", sprite.pixels.len());
-                println!("    • Expected pixels: {}", idle_anim.width * idle_anim.height * 4);
+                println!("    • Expected pixels: {}", warrior.width * warrior.height * 4);
             }
         }
 
         println!();
-        println!("All animations for '{}':", warrior.name);
-        for anim_name in warrior.animation_names() {
-            println!("  • {}", anim_name);
+        println!("All framegroups for '{}':", warrior.name);
+        for name in warrior.framegroup_names() {
+            println!("  • {}", name);
         }
     }
 
@@ -0,0 +1,742 @@
+use crate::loaded_types::{LoadedAnimation, LoadedAppearance, LoadedFrameGroup};
+use crate::types::{Direction, FrameEvent, PlaybackMode, StartFrame};
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hasher};
+
+/// Reprodutor de uma animação carregada
+///
+/// Responde "dado o tempo decorrido, qual sprite desenhar", respeitando a
+/// temporização por frame, o modo de reprodução, o número de ciclos e a
+/// política de frame inicial da animação. `update` retorna os eventos dos
+/// frames em que a animação entrou durante o passo de tempo.
+#[derive(Debug, Clone)]
+pub struct AnimationPlayer {
+    animation:       LoadedAnimation,
+    direction:       Option<Direction>,
+    frame:           usize,
+    /// Tempo restante (ms) até o próximo frame
+    frame_remaining: u32,
+    /// Sentido atual no modo ping-pong (true = para frente)
+    forward:         bool,
+    /// Ciclos completos até agora
+    cycles:          u32,
+    finished:        bool,
+    /// Eventos do frame inicial ainda não reportados
+    start_pending:   bool,
+    /// Tempo (ms) do último `update` que sobrou depois do fim da animação
+    overflow_ms:     u32,
+    rng_state:       u64,
+}
+
+impl AnimationPlayer {
+    /// Cria um reprodutor para a direção de um framegroup
+    ///
+    /// Se a direção não existir, usa a animação sem direção
+    /// (`get_animation_or_default`). Retorna `None` se nenhuma existir.
+    pub fn new(framegroup: &LoadedFrameGroup, direction: Option<Direction>) -> Option<Self> {
+        framegroup
+            .get_animation_or_default(direction)
+            .map(|animation| Self::from_animation(animation.clone(), direction))
+    }
+
+    /// Cria um reprodutor diretamente a partir de uma animação
+    pub fn from_animation(animation: LoadedAnimation, direction: Option<Direction>) -> Self {
+        let seed = RandomState::new().build_hasher().finish();
+        Self::with_seed(animation, direction, seed)
+    }
+
+    /// Cria um reprodutor com semente fixa para frames iniciais e durações aleatórias
+    ///
+    /// Útil para testes e replays determinísticos.
+    pub fn with_seed(animation: LoadedAnimation, direction: Option<Direction>, seed: u64) -> Self {
+        let mut player = Self {
+            animation,
+            direction,
+            frame: 0,
+            frame_remaining: 0,
+            forward: true,
+            cycles: 0,
+            finished: false,
+            start_pending: true,
+            overflow_ms: 0,
+            rng_state: seed ^ 0x9E37_79B9_7F4A_7C15,
+        };
+        player.reset();
+        player
+    }
+
+    /// Reinicia a animação a partir do frame inicial
+    pub fn reset(&mut self) {
+        let frame_count = self.frame_count();
+
+        self.frame = match self.animation.start_frame {
+            StartFrame::First => 0,
+            StartFrame::Random if frame_count > 0 => self.next_random() as usize % frame_count,
+            StartFrame::Random => 0,
+            StartFrame::Frame(index) => (index as usize).min(frame_count.saturating_sub(1)),
+        };
+        self.forward = true;
+        self.cycles = 0;
+        self.finished = false;
+        self.start_pending = true;
+        self.overflow_ms = 0;
+        self.frame_remaining = self.roll_frame_duration();
+    }
+
+    /// Avança a animação em `delta_ms` milissegundos
+    ///
+    /// Retorna os eventos de cada frame em que a animação entrou, em ordem.
+    /// Os eventos do frame inicial são reportados no primeiro `update`.
+    pub fn update(&mut self, delta_ms: u32) -> Vec<FrameEvent> {
+        let mut events = Vec::new();
+        let mut remaining = delta_ms;
+
+        if self.start_pending {
+            self.start_pending = false;
+            events.extend(self.animation.events_at(self.frame).cloned());
+        }
+
+        // Só animações sem nenhuma duração são estáticas; um frame com
+        // duração 0 no meio da animação é pulado, como em `frame_at`
+        if self.animation.is_static() {
+            return events;
+        }
+
+        while !self.finished {
+            if remaining < self.frame_remaining {
+                self.frame_remaining -= remaining;
+                break;
+            }
+
+            remaining -= self.frame_remaining;
+            self.advance_frame();
+
+            if !self.finished {
+                events.extend(self.animation.events_at(self.frame).cloned());
+            }
+        }
+
+        self.overflow_ms = if self.finished { remaining } else { 0 };
+        events
+    }
+
+    /// Troca a direção mantendo o frame e o tempo atuais
+    ///
+    /// Retorna false (sem alterar nada) se o framegroup não tiver animação
+    /// para a direção nem animação sem direção.
+    pub fn set_direction(&mut self, framegroup: &LoadedFrameGroup, direction: Option<Direction>) -> bool {
+        let Some(animation) = framegroup.get_animation_or_default(direction) else {
+            return false;
+        };
+
+        self.animation = animation.clone();
+        self.direction = direction;
+        self.frame = self.frame.min(self.frame_count().saturating_sub(1));
+        true
+    }
+
+    /// Retorna o sprite ID do frame atual
+    pub fn current_sprite_id(&self) -> Option<u32> {
+        self.animation.sprite_ids.get(self.frame).copied()
+    }
+
+    /// Retorna o índice do frame atual
+    pub fn current_frame(&self) -> usize {
+        self.frame
+    }
+
+    /// Retorna true quando a animação terminou (`once`, `once_and_hold` ou
+    /// `loop_count` atingido)
+    pub fn is_finished(&self) -> bool {
+        self.finished
+    }
+
+    /// Retorna o tempo (ms) do último `update` que passou do fim da animação
+    ///
+    /// Permite que a animação seguinte comece já adiantada, sem atrasar a
+    /// transição até o próximo `update`.
+    pub fn overflow_ms(&self) -> u32 {
+        self.overflow_ms
+    }
+
+    /// Retorna o número de ciclos completos
+    pub fn cycles(&self) -> u32 {
+        self.cycles
+    }
+
+    /// Retorna a direção solicitada
+    pub fn direction(&self) -> Option<Direction> {
+        self.direction
+    }
+
+    /// Retorna a animação em reprodução
+    pub fn animation(&self) -> &LoadedAnimation {
+        &self.animation
+    }
+
+    fn frame_count(&self) -> usize {
+        self.animation.sprite_ids.len()
+    }
+
+    /// Passa para o próximo frame de acordo com o modo de reprodução
+    fn advance_frame(&mut self) {
+        let last = self.frame_count().saturating_sub(1);
+
+        match self.animation.playback {
+            PlaybackMode::Loop => {
+                if self.frame < last {
+                    self.frame += 1;
+                } else if !self.complete_cycle() {
+                    self.frame = 0;
+                }
+            }
+            PlaybackMode::Once => {
+                if self.frame < last {
+                    self.frame += 1;
+                } else {
+                    self.frame = 0;
+                    self.finished = true;
+                }
+            }
+            PlaybackMode::OnceAndHold => {
+                if self.frame < last {
+                    self.frame += 1;
+                } else {
+                    self.finished = true;
+                }
+            }
+            PlaybackMode::PingPong => {
+                if last == 0 {
+                    self.complete_cycle();
+                } else if self.forward {
+                    if self.frame < last {
+                        self.frame += 1;
+                    } else {
+                        self.forward = false;
+                        self.frame -= 1;
+                    }
+                } else {
+                    self.frame = self.frame.saturating_sub(1);
+                    if self.frame == 0 {
+                        self.forward = true;
+                        self.complete_cycle();
+                    }
+                }
+            }
+        }
+
+        if !self.finished {
+            self.frame_remaining = self.roll_frame_duration();
+        }
+    }
+
+    /// Contabiliza um ciclo completo; retorna true se `loop_count` foi atingido
+    fn complete_cycle(&mut self) -> bool {
+        self.cycles += 1;
+        self.finished = self.animation.loop_count.is_some_and(|count| self.cycles >= count);
+        self.finished
+    }
+
+    /// Sorteia a duração do frame atual dentro do seu intervalo
+    fn roll_frame_duration(&mut self) -> u32 {
+        let timing = self.animation.frame_timing(self.frame);
+        if timing.is_random() {
+            // Em u64: `max - min + 1` estoura para {min: 0, max: u32::MAX}
+            let span = u64::from(timing.max - timing.min) + 1;
+            timing.min + (u64::from(self.next_random()) % span) as u32
+        } else {
+            timing.min
+        }
+    }
+
+    /// Gerador xorshift simples (evita dependência externa)
+    fn next_random(&mut self) -> u32 {
+        let mut x = self.rng_state.max(1);
+        x ^= x << 13;
+        x ^= x >> 7;
+        x ^= x << 17;
+        self.rng_state = x;
+        (x >> 32) as u32
+    }
+}
+
+/// Máquina de estados de framegroups de uma appearance
+///
+/// Cada estado é um framegroup (`idle`, `walk`, `attack`...). As transições
+/// vêm dos dados compilados: `next` ao terminar uma animação não-looping,
+/// `interruptible` para bloquear trocas e `fallback` quando o framegroup não
+/// tem a direção pedida. A appearance é passada em cada chamada para que a
+/// máquina não precise guardar (nem clonar) os dados carregados.
+#[derive(Debug, Clone)]
+pub struct AnimationStateMachine {
+    state:  String,
+    active: String,
+    player: AnimationPlayer,
+}
+
+impl AnimationStateMachine {
+    /// Cria a máquina no estado inicial informado
+    ///
+    /// Retorna `None` se o estado (ou seus fallbacks) não tiver animação
+    /// para a direção.
+    pub fn new(appearance: &LoadedAppearance, state: &str, direction: Option<Direction>) -> Option<Self> {
+        let framegroup = resolve_framegroup(appearance, state, direction)?;
+
+        Some(Self {
+            state:  state.to_string(),
+            active: framegroup.name.clone(),
+            player: AnimationPlayer::new(framegroup, direction)?,
+        })
+    }
+
+    /// Solicita a troca de estado respeitando `interruptible`
+    ///
+    /// Retorna true se o estado foi trocado (ou já era o atual). Um estado não
+    /// interrompível só pode ser trocado depois que sua animação termina; vale
+    /// o `interruptible` do framegroup em execução (o fallback, se usado).
+    pub fn request(&mut self, appearance: &LoadedAppearance, state: &str) -> bool {
+        if self.state == state {
+            return true;
+        }
+
+        let interruptible = appearance
+            .get_framegroup(&self.active)
+            .is_none_or(|framegroup| framegroup.interruptible);
+
+        if !interruptible && !self.player.is_finished() {
+            return false;
+        }
+
+        self.force(appearance, state)
+    }
+
+    /// Troca de estado imediatamente, ignorando `interruptible`
+    ///
+    /// Retorna false (sem alterar nada) se o estado não puder ser resolvido.
+    pub fn force(&mut self, appearance: &LoadedAppearance, state: &str) -> bool {
+        let direction = self.player.direction();
+
+        let Some(framegroup) = resolve_framegroup(appearance, state, direction) else {
+            return false;
+        };
+        let Some(player) = AnimationPlayer::new(framegroup, direction) else {
+            return false;
+        };
+
+        self.state = state.to_string();
+        self.active = framegroup.name.clone();
+        self.player = player;
+        true
+    }
+
+    /// Troca a direção mantendo o estado e o frame atuais
+    pub fn set_direction(&mut self, appearance: &LoadedAppearance, direction: Option<Direction>) -> bool {
+        let Some(framegroup) = resolve_framegroup(appearance, &self.state, direction) else {
+            return false;
+        };
+
+        if framegroup.name != self.active {
+            let Some(player) = AnimationPlayer::new(framegroup, direction) else {
+                return false;
+            };
+            self.active = framegroup.name.clone();
+            self.player = player;
+            return true;
+        }
+
+        self.player.set_direction(framegroup, direction)
+    }
+
+    /// Avança a animação e segue `next` quando ela termina
+    ///
+    /// O `next` é o do framegroup em reprodução (que difere do estado quando
+    /// houve fallback) e recebe o tempo que sobrou do passo. Retorna os
+    /// eventos de frame cruzados durante o passo de tempo.
+    pub fn update(&mut self, appearance: &LoadedAppearance, delta_ms: u32) -> Vec<FrameEvent> {
+        let mut events = self.player.update(delta_ms);
+
+        // Limita as transições por passo para não girar em uma cadeia de
+        // `next` cujas animações não consomem tempo
+        for _ in 0..appearance.framegroups.len() {
+            if !self.player.is_finished() {
+                break;
+            }
+
+            let Some(next) = appearance
+                .get_framegroup(&self.active)
+                .and_then(|framegroup| framegroup.next.clone())
+            else {
+                break;
+            };
+
+            let overflow_ms = self.player.overflow_ms();
+            if !self.force(appearance, &next) {
+                break;
+            }
+            events.extend(self.player.update(overflow_ms));
+        }
+
+        events
+    }
+
+    /// Retorna o estado atual (framegroup solicitado)
+    pub fn state(&self) -> &str {
+        &self.state
+    }
+
+    /// Retorna o framegroup em reprodução (difere de `state` quando houve fallback)
+    pub fn active_framegroup(&self) -> &str {
+        &self.active
+    }
+
+    /// Retorna o sprite ID do frame atual
+    pub fn current_sprite_id(&self) -> Option<u32> {
+        self.player.current_sprite_id()
+    }
+
+    /// Retorna o reprodutor da animação atual
+    pub fn player(&self) -> &AnimationPlayer {
+        &self.player
+    }
+}
+
+/// Resolve um framegroup seguindo a cadeia de `fallback` até encontrar um
+/// que tenha animação para a direção
+fn resolve_framegroup<'a>(
+    appearance: &'a LoadedAppearance, state: &str, direction: Option<Direction>,
+) -> Option<&'a LoadedFrameGroup> {
+    let mut framegroup = appearance.get_framegroup(state)?;
+
+    // Limita os saltos para não entrar em ciclo de fallbacks
+    for _ in 0..appearance.framegroups.len() {
+        if framegroup.get_animation_or_default(direction).is_some() {
+            return Some(framegroup);
+        }
+        framegroup = appearance.get_framegroup(framegroup.fallback.as_deref()?)?;
+    }
+
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::loaded_types::FrameTiming;
+    use crate::types::Metadata;
+    use std::collections::HashMap;
+
+    fn animation(frames: u32, duration: u32, playback: PlaybackMode) -> LoadedAnimation {
+        LoadedAnimation {
+            sprite_ids: (1..=frames).collect(),
+            duration: duration * frames,
+            frame_timings: vec![
+                FrameTiming {
+                    min: duration,
+                    max: duration,
+                };
+                frames as usize
+            ],
+            looped: playback.is_looping(),
+            playback,
+            loop_count: None,
+            start_frame: StartFrame::First,
+            synchronized: false,
+            events: Vec::new(),
+            attachments: HashMap::new(),
+            shapes: Vec::new(),
+            metadata: Metadata::default(),
+        }
+    }
+
+    fn sprites_over_time(player: &mut AnimationPlayer, steps: usize, delta_ms: u32) -> Vec<u32> {
+        (0..steps)
+            .map(|_| {
+                player.update(delta_ms);
+                player.current_sprite_id().unwrap()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_loop_wraps_around() {
+        let mut player = AnimationPlayer::from_animation(animation(3, 100, PlaybackMode::Loop), None);
+        assert_eq!(player.current_sprite_id(), Some(1));
+        assert_eq!(sprites_over_time(&mut player, 4, 100), vec![2, 3, 1, 2]);
+        assert!(!player.is_finished());
+        assert_eq!(player.cycles(), 1);
+    }
+
+    #[test]
+    fn test_large_delta_skips_frames() {
+        let mut player = AnimationPlayer::from_animation(animation(4, 100, PlaybackMode::Loop), None);
+        player.update(250);
+        assert_eq!(player.current_frame(), 2);
+        player.update(49);
+        assert_eq!(player.current_frame(), 2);
+        player.update(1);
+        assert_eq!(player.current_frame(), 3);
+    }
+
+    #[test]
+    fn test_once_and_hold_keeps_last_frame() {
+        let mut player = AnimationPlayer::from_animation(animation(3, 100, PlaybackMode::OnceAndHold), None);
+        assert_eq!(sprites_over_time(&mut player, 4, 100), vec![2, 3, 3, 3]);
+        assert!(player.is_finished());
+    }
+
+    #[test]
+    fn test_once_returns_to_first_frame() {
+        let mut player = AnimationPlayer::from_animation(animation(3, 100, PlaybackMode::Once), None);
+        assert_eq!(sprites_over_time(&mut player, 3, 100), vec![2, 3, 1]);
+        assert!(player.is_finished());
+    }
+
+    #[test]
+    fn test_ping_pong_and_loop_count() {
+        let mut anim = animation(3, 100, PlaybackMode::PingPong);
+        anim.loop_count = Some(2);
+        let mut player = AnimationPlayer::from_animation(anim, None);
+        assert_eq!(sprites_over_time(&mut player, 6, 100), vec![2, 3, 2, 1, 2, 3]);
+        assert!(!player.is_finished());
+        assert_eq!(sprites_over_time(&mut player, 3, 100), vec![2, 1, 1]);
+        assert!(player.is_finished());
+    }
+
+    #[test]
+    fn test_static_animation_never_advances() {
+        let mut player = AnimationPlayer::from_animation(animation(2, 0, PlaybackMode::Loop), None);
+        player.update(10_000);
+        assert_eq!(player.current_frame(), 0);
+    }
+
+    #[test]
+    fn test_zero_duration_frames_are_skipped() {
+        let mut anim = animation(3, 100, PlaybackMode::Loop);
+        anim.frame_timings[1] = FrameTiming { min: 0, max: 0 };
+        anim.events = vec![FrameEvent {
+            frame:   1,
+            name:    "blink".to_string(),
+            payload: None,
+        }];
+        let mut player = AnimationPlayer::from_animation(anim.clone(), None);
+
+        player.update(0);
+        let events = player.update(100);
+        assert_eq!(player.current_frame(), 2);
+        assert_eq!(events.len(), 1);
+        assert_eq!(sprites_over_time(&mut player, 2, 100), vec![1, 3]);
+
+        // O relógio global concorda com o reprodutor
+        assert_eq!(anim.frame_at(100), 2);
+
+        // Intervalo aleatório máximo não estoura
+        anim.frame_timings[1] = FrameTiming { min: 0, max: u32::MAX };
+        let mut player = AnimationPlayer::with_seed(anim, None, 7);
+        player.update(100);
+        assert!(player.current_frame() > 0);
+    }
+
+    #[test]
+    fn test_random_start_and_durations_stay_in_range() {
+        let mut anim = animation(4, 100, PlaybackMode::Loop);
+        anim.start_frame = StartFrame::Random;
+        anim.frame_timings[0] = FrameTiming { min: 50, max: 80 };
+
+        for seed in 0..32 {
+            let mut player = AnimationPlayer::with_seed(anim.clone(), None, seed);
+            assert!(player.current_frame() < 4);
+
+            player.reset();
+            if player.current_frame() == 0 {
+                player.update(49);
+                assert_eq!(player.current_frame(), 0);
+                player.update(31);
+                assert_eq!(player.current_frame(), 1);
+            }
+        }
+    }
+
+    #[test]
+    fn test_update_reports_crossed_events() {
+        let mut walk = animation(4, 100, PlaybackMode::Loop);
+        walk.events = vec![
+            FrameEvent {
+                frame:   0,
+                name:    "footstep".to_string(),
+                payload: Some("left".to_string()),
+            },
+            FrameEvent {
+                frame:   2,
+                name:    "footstep".to_string(),
+                payload: Some("right".to_string()),
+            },
+        ];
+        let mut player = AnimationPlayer::from_animation(walk, None);
+
+        let payloads =
+            |events: Vec<FrameEvent>| -> Vec<String> { events.into_iter().filter_map(|event| event.payload).collect() };
+
+        assert_eq!(payloads(player.update(50)), vec!["left"]);
+        assert!(player.update(50).is_empty());
+        assert_eq!(payloads(player.update(350)), vec!["right", "left"]);
+    }
+
+    #[test]
+    fn test_synchronized_frame_from_world_clock() {
+        let water = animation(3, 100, PlaybackMode::Loop);
+        assert_eq!(water.frame_at(0), 0);
+        assert_eq!(water.frame_at(250), 2);
+        assert_eq!(water.sprite_id_at(1_000_350), Some(2));
+
+        let fountain = animation(3, 100, PlaybackMode::PingPong);
+        let frames: Vec<usize> = (0..6).map(|step| fountain.frame_at(step * 100)).collect();
+        assert_eq!(frames, vec![0, 1, 2, 1, 0, 1]);
+
+        // Ping-pong com durações diferentes: 0 (100) 1 (50) 2 (200) 1 (50)
+        let mut uneven = animation(3, 0, PlaybackMode::PingPong);
+        uneven.frame_timings = [100, 50, 200]
+            .map(|duration| FrameTiming {
+                min: duration,
+                max: duration,
+            })
+            .to_vec();
+        let frames: Vec<usize> = [0, 120, 340, 360, 400].map(|time| uneven.frame_at(time)).to_vec();
+        assert_eq!(frames, vec![0, 1, 2, 1, 0]);
+
+        let mut door = animation(3, 100, PlaybackMode::OnceAndHold);
+        assert_eq!(door.frame_at(5_000), 2);
+        door.playback = PlaybackMode::Loop;
+        door.loop_count = Some(2);
+        assert_eq!(door.frame_at(550), 2);
+        assert_eq!(door.frame_at(5_000), 2);
+    }
+
+    #[test]
+    fn test_total_duration_does_not_overflow() {
+        let mut animation = animation(2, 100, PlaybackMode::Loop);
+        assert_eq!(animation.total_duration(), (200, 200));
+
+        animation.frame_timings[0] = FrameTiming { min: 0, max: u32::MAX };
+        assert_eq!(animation.total_duration(), (100, u32::MAX as u64 + 100));
+    }
+
+    #[test]
+    fn test_direction_fallback() {
+        let mut animations = HashMap::new();
+        animations.insert(None, animation(2, 100, PlaybackMode::Loop));
+        let mut north = animation(2, 100, PlaybackMode::Loop);
+        north.sprite_ids = vec![10, 11];
+        animations.insert(Some(Direction::North), north);
+
+        let framegroup = LoadedFrameGroup {
+            name: "walk".to_string(),
+            animations,
+            next: None,
+            interruptible: true,
+            fallback: None,
+            shapes: Vec::new(),
+            metadata: Metadata::default(),
+            light: None,
+        };
+
+        let mut player = AnimationPlayer::new(&framegroup, Some(Direction::North)).unwrap();
+        assert_eq!(player.current_sprite_id(), Some(10));
+
+        player.update(100);
+        assert!(player.set_direction(&framegroup, Some(Direction::East)));
+        assert_eq!(player.current_sprite_id(), Some(2));
+        assert_eq!(player.direction(), Some(Direction::East));
+    }
+
+    fn framegroup(name: &str, direction: Option<Direction>, anim: LoadedAnimation) -> LoadedFrameGroup {
+        LoadedFrameGroup {
+            name:          name.to_string(),
+            animations:    HashMap::from([(direction, anim)]),
+            next:          None,
+            interruptible: true,
+            fallback:      None,
+            shapes:        Vec::new(),
+            metadata:      Metadata::default(),
+            light:         None,
+        }
+    }
+
+    fn creature() -> LoadedAppearance {
+        let idle = framegroup("idle", None, animation(2, 100, PlaybackMode::Loop));
+
+        let mut attack = framegroup("attack", None, animation(3, 100, PlaybackMode::Once));
+        attack.next = Some("idle".to_string());
+        attack.interruptible = false;
+
+        let mut run = framegroup("run", Some(Direction::North), animation(2, 50, PlaybackMode::Loop));
+        run.fallback = Some("idle".to_string());
+
+        let mut dodge = framegroup("dodge", Some(Direction::North), animation(2, 50, PlaybackMode::Loop));
+        dodge.fallback = Some("attack".to_string());
+
+        LoadedAppearance {
+            framegroups: vec![idle, attack, run, dodge],
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_state_machine_follows_next_after_finish() {
+        let creature = creature();
+        let mut machine = AnimationStateMachine::new(&creature, "idle", None).unwrap();
+
+        assert!(machine.request(&creature, "attack"));
+        assert!(!machine.request(&creature, "idle"));
+        assert_eq!(machine.state(), "attack");
+
+        machine.update(&creature, 300);
+        assert_eq!(machine.state(), "idle");
+        assert!(machine.request(&creature, "attack"));
+
+        // Os 50ms que passam do fim do ataque já contam para o idle
+        machine.update(&creature, 350);
+        assert_eq!(machine.state(), "idle");
+        machine.update(&creature, 60);
+        assert_eq!(machine.current_sprite_id(), Some(2));
+    }
+
+    #[test]
+    fn test_state_machine_follows_next_of_fallback() {
+        let creature = creature();
+        let mut machine = AnimationStateMachine::new(&creature, "dodge", Some(Direction::South)).unwrap();
+        assert_eq!(machine.active_framegroup(), "attack");
+
+        machine.update(&creature, 300);
+        assert_eq!(machine.state(), "idle");
+        assert_eq!(machine.active_framegroup(), "idle");
+    }
+
+    #[test]
+    fn test_state_machine_respects_non_interruptible_fallback() {
+        let creature = creature();
+        let mut machine = AnimationStateMachine::new(&creature, "dodge", Some(Direction::South)).unwrap();
+        assert_eq!(machine.active_framegroup(), "attack");
+
+        assert!(!machine.request(&creature, "run"));
+        assert_eq!(machine.state(), "dodge");
+
+        machine.update(&creature, 250);
+        assert!(!machine.request(&creature, "run"));
+        assert!(machine.force(&creature, "run"));
+        assert_eq!(machine.state(), "run");
+    }
+
+    #[test]
+    fn test_state_machine_fallback_for_missing_direction() {
+        let creature = creature();
+        let mut machine = AnimationStateMachine::new(&creature, "run", Some(Direction::North)).unwrap();
+        assert_eq!(machine.active_framegroup(), "run");
+
+        assert!(machine.set_direction(&creature, Some(Direction::South)));
+        assert_eq!(machine.state(), "run");
+        assert_eq!(machine.active_framegroup(), "idle");
+
+        assert!(!machine.force(&creature, "swim"));
+        assert_eq!(machine.state(), "run");
+    }
+}
@@ -0,0 +1,372 @@
+use crate::error::{AppearanceError, Result};
+use crate::types::{
+    Animation, Appearance, AppearancesFile, AttachmentPoint, Category, FrameGroup, FrameGroupLight, Light, StartFrame,
+};
+use std::collections::{HashMap, HashSet};
+
+/// Um problema encontrado em um item (appearance, framegroup ou animação)
+///
+/// As verificações deste módulo são usadas tanto por `compile_appearances`,
+/// que para no primeiro problema, quanto por `validate_appearances`, que
+/// reporta todos.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Problem {
+    /// Campo com problema, relativo ao item verificado
+    pub field:   &'static str,
+    pub message: String,
+}
+
+impl Problem {
+    fn new(field: &'static str, message: String) -> Self {
+        Problem { field, message }
+    }
+}
+
+impl From<Problem> for AppearanceError {
+    fn from(problem: Problem) -> Self {
+        AppearanceError::InvalidData(problem.message).in_field(problem.field)
+    }
+}
+
+/// Retorna o primeiro problema como erro (usado pela compilação)
+pub(crate) fn first(problems: Vec<Problem>) -> Result<()> {
+    match problems.into_iter().next() {
+        Some(problem) => Err(problem.into()),
+        None => Ok(()),
+    }
+}
+
+/// Appearances cujo nome já foi usado por outra, com o índice da repetida
+pub(crate) fn duplicate_names(appearances_file: &AppearancesFile) -> Vec<(usize, AppearanceError)> {
+    let mut names: HashMap<&str, u32> = HashMap::new();
+    let mut duplicates = Vec::new();
+
+    for (index, appearance) in appearances_file.appearances.iter().enumerate() {
+        if let Some(first_id) = names.insert(&appearance.name, appearance.id) {
+            duplicates.push((
+                index,
+                AppearanceError::DuplicateName {
+                    name: appearance.name.clone(),
+                    first_id,
+                    second_id: appearance.id,
+                },
+            ));
+        }
+    }
+
+    duplicates
+}
+
+/// Appearances cujo ID já foi usado por outra, com o índice da repetida
+pub(crate) fn duplicate_ids(appearances_file: &AppearancesFile) -> Vec<(usize, AppearanceError)> {
+    let mut ids: HashMap<u32, &str> = HashMap::new();
+    let mut duplicates = Vec::new();
+
+    for (index, appearance) in appearances_file.appearances.iter().enumerate() {
+        if let Some(first) = ids.insert(appearance.id, &appearance.name) {
+            duplicates.push((
+                index,
+                AppearanceError::DuplicateId {
+                    id:     appearance.id as u64,
+                    first:  first.to_string(),
+                    second: appearance.name.clone(),
+                },
+            ));
+        }
+    }
+
+    duplicates
+}
+
+/// Tamanho da célula, footprint, propriedades, luz e nomes de framegroups repetidos
+pub(crate) fn appearance_problems(appearance: &Appearance) -> Vec<Problem> {
+    let mut problems = Vec::new();
+
+    for (field, value) in [
+        ("size", appearance.size),
+        ("width", appearance.width),
+        ("height", appearance.height),
+    ] {
+        if value == Some(0) {
+            problems.push(Problem::new(field, format!("`{}` must be greater than zero", field)));
+        }
+    }
+
+    if appearance.cell_size().is_none() {
+        problems.push(Problem::new(
+            "size",
+            "Appearance must define `size` or both `width` and `height`".to_string(),
+        ));
+    }
+
+    if appearance.footprint.width == 0 || appearance.footprint.height == 0 {
+        problems.push(Problem::new("footprint", "`footprint` must be at least 1x1".to_string()));
+    }
+
+    if appearance.properties.ground_speed.is_some() && appearance.category != Some(Category::Ground) {
+        problems.push(Problem::new(
+            "properties",
+            "`ground_speed` is only allowed when the category is `ground`".to_string(),
+        ));
+    }
+
+    problems.extend(appearance.light.as_ref().and_then(light_problem));
+
+    let mut names = HashSet::new();
+    for framegroup in &appearance.framegroups {
+        if !names.insert(framegroup.name.as_str()) {
+            problems.push(Problem::new(
+                "framegroups",
+                format!("Duplicate framegroup name '{}'", framegroup.name),
+            ));
+        }
+    }
+
+    problems
+}
+
+/// Animações vazias ou com chaves misturadas, transições, luz, frames das
+/// formas e as regras de `rotation` de um framegroup
+pub(crate) fn framegroup_problems(appearance: &Appearance, framegroup: &FrameGroup) -> Vec<Problem> {
+    let mut problems = Vec::new();
+
+    if framegroup.animations.is_empty() {
+        problems.push(Problem::new("animations", "Framegroup has no animations".to_string()));
+    } else if is_mixed(framegroup) {
+        problems.push(Problem::new(
+            "animations",
+            "Framegroup mixes directional animations with a \"null\" (non-directional) one".to_string(),
+        ));
+    }
+
+    for (field, target) in [("next", &framegroup.next), ("fallback", &framegroup.fallback)] {
+        if let Some(target) = target
+            && !appearance.framegroups.iter().any(|fg| &fg.name == target)
+        {
+            problems.push(Problem::new(
+                field,
+                format!("References unknown framegroup '{}'", target),
+            ));
+        }
+    }
+
+    match &framegroup.light {
+        Some(FrameGroupLight::Light(light)) => problems.extend(light_problem(light)),
+        Some(FrameGroupLight::Disabled(disabled)) if disabled.enabled => problems.push(Problem::new(
+            "light",
+            "`enabled: true` is not a light: define `intensity`, `color` and `radius` instead".to_string(),
+        )),
+        _ => {}
+    }
+
+    // Formas do framegroup valem para todas as direções: o frame precisa
+    // existir em pelo menos uma delas
+    let frames = framegroup.animations.values().map(frame_count).max().unwrap_or(0);
+    for shape in &framegroup.shapes {
+        if let Some(frame) = shape.frame
+            && frame as usize >= frames
+        {
+            problems.push(Problem::new(
+                "shapes",
+                format!(
+                    "Collision shape is on frame {} but the longest animation has {} frames",
+                    frame, frames
+                ),
+            ));
+        }
+    }
+
+    if framegroup.rotation.is_some() {
+        if framegroup.animations.len() != 1 {
+            problems.push(Problem::new(
+                "animations",
+                "Framegroup uses rotation and must define exactly one animation".to_string(),
+            ));
+        }
+        if framegroup
+            .animations
+            .values()
+            .any(|animation| animation.sprite_ids.is_some())
+        {
+            problems.push(Problem::new(
+                "rotation",
+                "Framegroup uses rotation and cannot use `sprite_ids`".to_string(),
+            ));
+        }
+
+        // As direções geradas não giram formas nem anchors junto com as sprites
+        if !framegroup.shapes.is_empty() {
+            problems.push(Problem::new(
+                "shapes",
+                "Framegroup uses rotation and cannot define collision shapes".to_string(),
+            ));
+        }
+        if framegroup
+            .animations
+            .values()
+            .any(|animation| !animation.shapes.is_empty() || !animation.attachments.is_empty())
+        {
+            problems.push(Problem::new(
+                "animations",
+                "Framegroup uses rotation and its animation cannot define `shapes` or `attachments`".to_string(),
+            ));
+        }
+    }
+
+    problems
+}
+
+/// O framegroup tem uma animação `null` junto com animações direcionais
+pub(crate) fn is_mixed(framegroup: &FrameGroup) -> bool {
+    framegroup.animations.contains_key(&None) && framegroup.animations.len() > 1
+}
+
+/// Número de frames de uma animação (tamanho de `sprite_ids` ou `frame_count`)
+pub(crate) fn frame_count(animation: &Animation) -> usize {
+    animation
+        .sprite_ids
+        .as_ref()
+        .map_or(animation.frame_count as usize, Vec::len)
+}
+
+/// Frames, `loop_count`, `start_frame`, eventos, temporização, anchors e formas de uma animação
+pub(crate) fn animation_problems(animation: &Animation) -> Vec<Problem> {
+    let mut problems = Vec::new();
+    let frames = frame_count(animation);
+
+    match &animation.sprite_ids {
+        Some(sprite_ids) if sprite_ids.is_empty() => {
+            problems.push(Problem::new("sprite_ids", "`sprite_ids` must not be empty".to_string()));
+        }
+        None if animation.frame_count == 0 => {
+            problems.push(Problem::new(
+                "frame_count",
+                "`frame_count` must be greater than zero".to_string(),
+            ));
+        }
+        _ => {}
+    }
+
+    // 0 é o marcador de "infinito" no .dat
+    match animation.loop_count {
+        Some(0) => problems.push(Problem::new(
+            "loop_count",
+            "`loop_count` must be greater than zero (omit it to loop forever)".to_string(),
+        )),
+        Some(_) if !animation.playback_mode().is_looping() => problems.push(Problem::new(
+            "loop_count",
+            "`loop_count` is only allowed with `loop` or `ping_pong` playback".to_string(),
+        )),
+        _ => {}
+    }
+
+    if let StartFrame::Frame(index) = animation.start_frame
+        && index as usize >= frames
+    {
+        problems.push(Problem::new(
+            "start_frame",
+            format!(
+                "start_frame {} is out of range for an animation with {} frames",
+                index, frames
+            ),
+        ));
+    }
+
+    for event in &animation.events {
+        if event.frame as usize >= frames {
+            problems.push(Problem::new(
+                "events",
+                format!(
+                    "Event '{}' is on frame {} but the animation has {} frames",
+                    event.name, event.frame, frames
+                ),
+            ));
+        }
+    }
+
+    if animation.duration.is_some() && animation.frame_duration.is_some() {
+        problems.push(Problem::new(
+            "frame_duration",
+            "`duration` (total) and `frame_duration` (per frame) cannot be used together".to_string(),
+        ));
+    }
+
+    if let Some(frame_durations) = &animation.frame_durations {
+        if frame_durations.len() != frames {
+            problems.push(Problem::new(
+                "frame_durations",
+                format!(
+                    "frame_durations has {} entries but the animation has {} frames",
+                    frame_durations.len(),
+                    frames
+                ),
+            ));
+        }
+        for (min, max) in frame_durations.iter().map(|frame_duration| frame_duration.range()) {
+            if min > max {
+                problems.push(Problem::new(
+                    "frame_durations",
+                    format!("Invalid frame duration range: min {} is greater than max {}", min, max),
+                ));
+            }
+        }
+    }
+
+    // Ordem estável dos anchors nas mensagens
+    let mut attachments: Vec<_> = animation.attachments.iter().collect();
+    attachments.sort_by_key(|(name, _)| *name);
+    for (name, attachment) in attachments {
+        if let AttachmentPoint::PerFrame(points) = attachment
+            && points.len() != frames
+        {
+            problems.push(Problem::new(
+                "attachments",
+                format!(
+                    "Attachment '{}' has {} points but the animation has {} frames",
+                    name,
+                    points.len(),
+                    frames
+                ),
+            ));
+        }
+    }
+
+    for shape in &animation.shapes {
+        if let Some(frame) = shape.frame
+            && frame as usize >= frames
+        {
+            problems.push(Problem::new(
+                "shapes",
+                format!(
+                    "Collision shape is on frame {} but the animation has {} frames",
+                    frame, frames
+                ),
+            ));
+        }
+    }
+
+    problems
+}
+
+/// Intensidade finita e não negativa e `flicker` com período e amplitude válidos
+fn light_problem(light: &Light) -> Option<Problem> {
+    if !light.intensity.is_finite() || light.intensity < 0.0 {
+        return Some(Problem::new(
+            "light",
+            format!("Light intensity must be a finite number >= 0, got {}", light.intensity),
+        ));
+    }
+
+    let flicker = light.flicker?;
+    if flicker.period == 0 || !(0.0..=1.0).contains(&flicker.amount) {
+        return Some(Problem::new(
+            "light",
+            format!(
+                "Light flicker needs a period > 0 and an amount between 0 and 1, got period {} and amount {}",
+                flicker.period, flicker.amount
+            ),
+        ));
+    }
+
+    None
+}
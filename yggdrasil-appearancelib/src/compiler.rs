@@ -1,182 +1,641 @@
-use crate::error::Result;
-use crate::sprite::slice_spritesheet;
-use crate::types::{AppearancesFile, Direction, SpriteData};
-use byteorder::{LittleEndian, WriteBytesExt};
-use std::collections::HashMap;
+use crate::check;
+use crate::error::{AppearanceError, Result};
+use crate::mask::SpriteMask;
+use crate::resolver::PathResolver;
+use crate::sprite::{
+    RgbaSprite, average_color, dominant_color, generate_rotated_directions_rgba, slice_spritesheet_rgba,
+};
+use crate::types::{
+    Animation, Appearance, AppearancesFile, AttachmentPoint, Category, CollisionShape, Direction, FrameDuration,
+    FrameGroup, FrameGroupLight, Light, Metadata, PlaybackMode, Properties, ShapeKind, SpriteData, StartFrame,
+};
+use byteorder::{LittleEndian, ReadBytesExt, WriteBytesExt};
+use flate2::read::GzDecoder;
+use std::collections::{HashMap, HashSet};
 use std::fs;
-use std::io::{Cursor, Write};
+use std::io::{Cursor, Read, Write};
 use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+/// Assinatura no início do appearances.dat
+pub(crate) const DAT_MAGIC: [u8; 4] = *b"YGAP";
+
+/// Versão do layout binário do appearances.dat (incrementar a cada mudança
+/// nos registros; o loader rejeita arquivos de outra versão)
+pub(crate) const DAT_FORMAT_VERSION: u32 = 2;
 
 /// Resultado da compilação
 pub struct CompilationResult {
-    pub appearances_count: usize,
-    pub sprites_count:     usize,
-    pub dat_size:          usize,
-    pub total_spr_size:    usize,
+    pub appearances_count:    usize,
+    pub sprites_count:        usize,
+    /// Sprites pré-existentes de `sprite_ids` (não são recompactadas)
+    pub reused_sprites_count: usize,
+    pub dat_size:             usize,
+    pub total_spr_size:       usize,
+}
+
+/// Opções de compilação
+#[derive(Debug, Clone, Default)]
+pub struct CompileOptions {
+    /// Gera o masks.dat com uma máscara de alpha de 1 bit e o bounding box
+    /// opaco de cada sprite (hit test sem descompactar os pixels)
+    pub alpha_masks:     bool,
+    /// Pixels com alpha maior que este valor são considerados opacos
+    pub alpha_threshold: u8,
+    /// Calcula a cor do minimapa a partir do primeiro frame de cada
+    /// appearance (`minimap_color` no JSON sempre tem prioridade)
+    pub minimap_color:   MinimapColorMode,
+    /// Raízes de busca de spritesheets, consultadas depois da pasta do
+    /// arquivo de origem e de `base_path` (ver `PathResolver`)
+    pub asset_roots:     Vec<PathBuf>,
+    /// Pasta com os .spr usados por `sprite_ids` (padrão: a pasta de
+    /// output); de outra pasta, os arquivos são ligados (hard link) ou
+    /// copiados para o output sem serem recompactados
+    pub sprites_path:    Option<PathBuf>,
+}
+
+/// Modo de cálculo automático da cor do minimapa
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum MinimapColorMode {
+    /// Apenas cores informadas no JSON
+    #[default]
+    Off,
+    /// Média dos pixels opacos
+    Average,
+    /// Cor mais frequente entre os pixels opacos
+    Dominant,
+}
+
+impl FromStr for MinimapColorMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s {
+            "off" => Ok(MinimapColorMode::Off),
+            "average" => Ok(MinimapColorMode::Average),
+            "dominant" => Ok(MinimapColorMode::Dominant),
+            _ => Err(format!(
+                "invalid minimap color mode `{}`, expected off, average or dominant",
+                s
+            )),
+        }
+    }
+}
+
+/// Salva as sprites compiladas, atribuindo IDs sequenciais
+struct SpriteWriter<'a> {
+    output_path:    &'a Path,
+    /// Pasta de onde vêm as sprites de `sprite_ids`
+    sprites_path:   PathBuf,
+    /// A pasta de sprites não é o output, então os reaproveitados são ligados a ele
+    link_reused:    bool,
+    options:        &'a CompileOptions,
+    next_sprite_id: u32,
+    /// IDs informados em `sprite_ids`, que não são atribuídos a sprites novas
+    reserved:       HashSet<u32>,
+    /// IDs reservados já conferidos na pasta de sprites
+    reused:         HashSet<u32>,
+    count:          usize,
+    total_size:     usize,
+    masks:          Vec<(u32, SpriteMask)>,
+}
+
+impl SpriteWriter<'_> {
+    /// Salva as sprites como arquivos .spr e retorna os IDs atribuídos
+    fn save(&mut self, sprites: &[RgbaSprite]) -> Vec<u32> {
+        sprites
+            .iter()
+            .map(|sprite| {
+                while self.reserved.contains(&self.next_sprite_id) {
+                    self.next_sprite_id += 1;
+                }
+
+                let sprite_id = self.next_sprite_id;
+                save_sprite_file(self.output_path, sprite_id, &sprite.data).ok();
+                self.total_size += sprite.data.compressed_pixels.len();
+                self.count += 1;
+                self.next_sprite_id += 1;
+
+                if self.options.alpha_masks {
+                    let mask = SpriteMask::from_rgba(
+                        sprite.data.width,
+                        sprite.data.height,
+                        &sprite.pixels,
+                        self.options.alpha_threshold,
+                    );
+                    self.masks.push((sprite_id, mask));
+                }
+
+                sprite_id
+            })
+            .collect()
+    }
+
+    /// Confere as sprites pré-existentes referenciadas por `sprite_ids`
+    ///
+    /// Cada ID precisa ter um .spr na pasta de sprites. Se ela não for o
+    /// output, o arquivo é ligado ao output para que o loader o encontre; o
+    /// conteúdo nunca é regravado. Os pixels só são lidos quando necessários
+    /// (masks.dat e cor do minimapa, ver `load_reused`).
+    fn reuse(&mut self, sprite_ids: &[u32]) -> Result<()> {
+        for &sprite_id in sprite_ids {
+            let filepath = reused_sprite_path(&self.sprites_path, sprite_id)?;
+
+            if !self.reused.insert(sprite_id) {
+                continue;
+            }
+
+            if self.link_reused {
+                link_sprite_file(&filepath, &self.output_path.join(format!("{:05}.spr", sprite_id)))?;
+            }
+
+            if self.options.alpha_masks {
+                let sprite = load_sprite_file(&filepath)?;
+                let mask = SpriteMask::from_rgba(
+                    sprite.data.width,
+                    sprite.data.height,
+                    &sprite.pixels,
+                    self.options.alpha_threshold,
+                );
+                self.masks.push((sprite_id, mask));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Lê uma sprite pré-existente com seus pixels RGBA
+    fn load_reused(&self, sprite_id: u32) -> Result<RgbaSprite> {
+        load_sprite_file(&reused_sprite_path(&self.sprites_path, sprite_id)?)
+    }
 }
 
 /// Compila o appearances.json em arquivos binários
+///
+/// Os erros vêm em `AppearanceError::Context`; use `root()` para o erro original.
 pub fn compile_appearances<P: AsRef<Path>>(
     appearances_file: &AppearancesFile, base_path: P, output_path: P,
 ) -> Result<CompilationResult> {
-    let base_path = base_path.as_ref();
+    compile_appearances_with_options(appearances_file, base_path, output_path, &CompileOptions::default())
+}
+
+/// Compila o appearances.json em arquivos binários com opções adicionais
+pub fn compile_appearances_with_options<P: AsRef<Path>>(
+    appearances_file: &AppearancesFile, base_path: P, output_path: P, options: &CompileOptions,
+) -> Result<CompilationResult> {
+    let resolver = PathResolver::new(&base_path).with_roots(&options.asset_roots)?;
     let output_path = output_path.as_ref();
+    let sprites_path = options
+        .sprites_path
+        .clone()
+        .unwrap_or_else(|| output_path.to_path_buf());
+
+    // IDs e nomes precisam ser únicos para os índices do database
+    if let Some((_, error)) = check::duplicate_ids(appearances_file).into_iter().next() {
+        return Err(error);
+    }
+    if let Some((_, error)) = check::duplicate_names(appearances_file).into_iter().next() {
+        return Err(error);
+    }
 
     // Cria a pasta de output se não existir
     fs::create_dir_all(output_path)?;
 
-    let mut next_sprite_id = 1u32;
-
     // Buffer para o arquivo .dat
     let mut dat_buffer = Cursor::new(Vec::new());
 
-    // Escreve header do .dat
+    // Escreve header do .dat: assinatura, versão do formato, versão do conteúdo e quantidade
+    dat_buffer.write_all(&DAT_MAGIC)?;
+    dat_buffer.write_u32::<LittleEndian>(DAT_FORMAT_VERSION)?;
     dat_buffer.write_u32::<LittleEndian>(appearances_file.version)?;
     dat_buffer.write_u32::<LittleEndian>(appearances_file.appearances.len() as u32)?;
 
-    let mut total_sprites = 0;
-    let mut total_spr_size = 0;
+    // Compara os caminhos canônicos: `out` e `./out` são a mesma pasta
+    let link_reused = match (fs::canonicalize(&sprites_path), fs::canonicalize(output_path)) {
+        (Ok(sprites), Ok(output)) => sprites != output,
+        _ => true,
+    };
+
+    let mut sprite_writer = SpriteWriter {
+        output_path,
+        sprites_path,
+        link_reused,
+        options,
+        next_sprite_id: 1,
+        reserved: reserved_sprite_ids(appearances_file),
+        reused: HashSet::new(),
+        count: 0,
+        total_size: 0,
+        masks: Vec::new(),
+    };
 
     // Processa cada appearance
-    for appearance in &appearances_file.appearances {
-        // Escreve dados da appearance
-        dat_buffer.write_u32::<LittleEndian>(appearance.id)?;
+    for (index, appearance) in appearances_file.appearances.iter().enumerate() {
+        compile_appearance(&mut dat_buffer, appearance, &resolver, &mut sprite_writer)
+            .map_err(|error| error.in_appearance(index, appearance))?;
+    }
 
-        // Nome
-        write_string(&mut dat_buffer, &appearance.name)?;
+    // Salva o arquivo .dat
+    let dat_path = output_path.join("appearances.dat");
+    let dat_bytes = dat_buffer.into_inner();
+    fs::write(&dat_path, &dat_bytes)?;
 
-        // Offset
-        dat_buffer.write_i32::<LittleEndian>(appearance.offset.x)?;
-        dat_buffer.write_i32::<LittleEndian>(appearance.offset.y)?;
+    // Salva as máscaras de alpha
+    if options.alpha_masks {
+        save_masks_file(output_path, &sprite_writer.masks)?;
+    }
 
-        // Size
-        dat_buffer.write_u32::<LittleEndian>(appearance.size)?;
+    Ok(CompilationResult {
+        appearances_count:    appearances_file.appearances.len(),
+        sprites_count:        sprite_writer.count,
+        reused_sprites_count: sprite_writer.reused.len(),
+        dat_size:             dat_bytes.len(),
+        total_spr_size:       sprite_writer.total_size,
+    })
+}
 
-        // Número de framegroups
-        dat_buffer.write_u32::<LittleEndian>(appearance.framegroups.len() as u32)?;
+/// Escreve uma appearance no .dat, salvando as sprites dos seus framegroups
+fn compile_appearance<W: Write>(
+    writer: &mut W, appearance: &Appearance, resolver: &PathResolver, sprite_writer: &mut SpriteWriter,
+) -> Result<()> {
+    check::first(check::appearance_problems(appearance))?;
 
-        // Processa cada framegroup
-        for framegroup in &appearance.framegroups {
-            // Nome do framegroup
-            write_string(&mut dat_buffer, &framegroup.name)?;
+    // Escreve dados da appearance
+    writer.write_u32::<LittleEndian>(appearance.id)?;
 
-            // Resolve o path completo do spritesheet
-            let spritesheet_path = if framegroup.spritesheet.starts_with("assets/") {
-                base_path.join(&framegroup.spritesheet)
-            } else {
-                PathBuf::from(&framegroup.spritesheet)
-            };
+    // Nome
+    write_string(writer, &appearance.name)?;
 
-            // Número de animações (direções)
-            dat_buffer.write_u32::<LittleEndian>(framegroup.animations.len() as u32)?;
-
-            // Processa cada animação/direção
-            for (direction, animation) in &framegroup.animations {
-                // Escreve a direção (ou None se não houver)
-                if let Some(dir) = direction {
-                    dat_buffer.write_u8(1)?; // Tem direção
-                    dat_buffer.write_u8(direction_to_u8(*dir))?;
-                } else {
-                    dat_buffer.write_u8(0)?; // Sem direção
-                }
+    // Tags
+    writer.write_u32::<LittleEndian>(appearance.tags.len() as u32)?;
+    for tag in &appearance.tags {
+        write_string(writer, tag)?;
+    }
 
-                // Determina o número de direções para recorte
-                let num_directions = if direction.is_some() {
-                    // Se há uma direção específica, assumimos que o spritesheet
-                    // contém todas as direções em linhas
-                    framegroup.animations.len() as u32
-                } else {
-                    0
-                };
-
-                // Recorta o spritesheet em sprites individuais
-                let sprites = slice_spritesheet(
-                    &spritesheet_path,
-                    appearance.size,
-                    animation.frame_count,
-                    num_directions,
-                    framegroup.orientation,
-                )?;
-
-                // Determina quais sprites pertencem a esta animação específica
-                let sprite_ids: Vec<u32> = if let Some(dir) = direction {
-                    // Calcula os índices baseado na direção e orientação
-                    let direction_index = calculate_direction_row(*dir, &framegroup.animations);
-
-                    // Para orientação Horizontal: sprites são organizadas por frame
-                    // Frame 0: [N][E][S][W], Frame 1: [N][E][S][W], etc.
-                    // Para orientação Vertical: sprites são organizadas por direção
-                    // North: [N1][N2][N3]..., East: [E1][E2][E3]..., etc.
-                    let sprite_indices: Vec<usize> = match framegroup.orientation {
-                        crate::types::Orientation::Horizontal => {
-                            // Para cada frame, pega a sprite na coluna da direção
-                            (0..animation.frame_count)
-                                .map(|frame| (frame as usize * num_directions as usize) + direction_index)
-                                .collect()
-                        }
-                        crate::types::Orientation::Vertical => {
-                            // Pega todas as sprites da linha da direção
-                            let start_idx = direction_index * animation.frame_count as usize;
-                            (start_idx..start_idx + animation.frame_count as usize).collect()
-                        }
-                    };
-
-                    // Salva as sprites
-                    sprite_indices
-                        .iter()
-                        .map(|&idx| {
-                            let sprite_data = &sprites[idx];
-                            let sprite_id = next_sprite_id;
-                            save_sprite_file(output_path, sprite_id, sprite_data).ok();
-                            total_spr_size += sprite_data.compressed_pixels.len();
-                            total_sprites += 1;
-                            next_sprite_id += 1;
-                            sprite_id
-                        })
-                        .collect()
-                } else {
-                    // Sem direção, todas as sprites são dessa animação
-                    sprites
-                        .iter()
-                        .map(|sprite_data| {
-                            let sprite_id = next_sprite_id;
-                            save_sprite_file(output_path, sprite_id, sprite_data).ok();
-                            total_spr_size += sprite_data.compressed_pixels.len();
-                            total_sprites += 1;
-                            next_sprite_id += 1;
-                            sprite_id
-                        })
-                        .collect()
-                };
+    // Offset
+    writer.write_i32::<LittleEndian>(appearance.offset.x)?;
+    writer.write_i32::<LittleEndian>(appearance.offset.y)?;
 
-                // Escreve o número de sprite IDs
-                dat_buffer.write_u32::<LittleEndian>(sprite_ids.len() as u32)?;
+    // Dimensões da célula (largura, altura), já verificadas em `appearance_problems`
+    let (cell_width, cell_height) = appearance.cell_size().unwrap_or_default();
+    writer.write_u32::<LittleEndian>(cell_width)?;
+    writer.write_u32::<LittleEndian>(cell_height)?;
 
-                // Escreve cada sprite ID
-                for sprite_id in sprite_ids {
-                    dat_buffer.write_u32::<LittleEndian>(sprite_id)?;
-                }
+    // Footprint em tiles
+    writer.write_u32::<LittleEndian>(appearance.footprint.width)?;
+    writer.write_u32::<LittleEndian>(appearance.footprint.height)?;
+
+    // Categoria e propriedades de gameplay
+    write_properties(writer, appearance)?;
+
+    // Metadata livre
+    write_metadata(writer, &appearance.metadata)?;
 
-                // Escreve a duração
-                dat_buffer.write_u32::<LittleEndian>(animation.duration.unwrap_or(0))?;
+    // Luz
+    write_light(writer, appearance.light.as_ref())?;
 
-                // Escreve o flag looped (1 = true, 0 = false)
-                dat_buffer.write_u8(if animation.looped.unwrap_or(true) { 1 } else { 0 })?;
+    // Número de framegroups
+    writer.write_u32::<LittleEndian>(appearance.framegroups.len() as u32)?;
+
+    // Primeiro frame da appearance (primeiro framegroup, menor direção),
+    // usado para calcular a cor do minimapa
+    let mut first_frame: Option<(u8, RgbaSprite)> = None;
+
+    // Processa cada framegroup
+    for (framegroup_index, framegroup) in appearance.framegroups.iter().enumerate() {
+        let first_frame = (framegroup_index == 0).then_some(&mut first_frame);
+
+        compile_framegroup(
+            writer,
+            appearance,
+            framegroup,
+            (cell_width, cell_height),
+            resolver,
+            sprite_writer,
+            first_frame,
+        )
+        .map_err(|error| error.in_framegroup(framegroup_index, &framegroup.name))?;
+    }
+
+    // Cor do minimapa (informada no JSON ou calculada do primeiro frame)
+    let options = sprite_writer.options;
+    let minimap_color = appearance.minimap_color.or_else(|| {
+        let (_, sprite) = first_frame?;
+        match options.minimap_color {
+            MinimapColorMode::Off => None,
+            MinimapColorMode::Average => average_color(&sprite.pixels, options.alpha_threshold),
+            MinimapColorMode::Dominant => dominant_color(&sprite.pixels, options.alpha_threshold),
+        }
+    });
+    match minimap_color {
+        Some(color) => {
+            writer.write_u8(1)?;
+            writer.write_all(&[color.r, color.g, color.b])?;
+        }
+        None => writer.write_u8(0)?,
+    }
+
+    Ok(())
+}
+
+/// Escreve um framegroup no .dat, recortando e salvando as sprites de cada direção
+///
+/// `first_frame` só é informado para o primeiro framegroup da appearance.
+fn compile_framegroup<W: Write>(
+    writer: &mut W, appearance: &Appearance, framegroup: &FrameGroup, (cell_width, cell_height): (u32, u32),
+    resolver: &PathResolver, sprite_writer: &mut SpriteWriter, mut first_frame: Option<&mut Option<(u8, RgbaSprite)>>,
+) -> Result<()> {
+    check::first(check::framegroup_problems(appearance, framegroup))?;
+
+    // Nome do framegroup
+    write_string(writer, &framegroup.name)?;
+
+    // Transições (next, interruptible, fallback)
+    write_optional_string(writer, framegroup.next.as_deref())?;
+    writer.write_u8(if framegroup.interruptible { 1 } else { 0 })?;
+    write_optional_string(writer, framegroup.fallback.as_deref())?;
+
+    // Formas de colisão do framegroup (todas as direções)
+    write_shapes(writer, &framegroup.shapes).map_err(|error| error.in_field("shapes"))?;
+
+    // Metadata livre do framegroup
+    write_metadata(writer, &framegroup.metadata)?;
+
+    // Luz do framegroup
+    write_framegroup_light(writer, framegroup.light.as_ref())?;
+
+    // Resolve o path completo do spritesheet (opcional se todas as animações têm `sprite_ids`)
+    let resolve_spritesheet = || {
+        let Some(spritesheet) = framegroup.spritesheet.as_deref() else {
+            return Err(AppearanceError::InvalidData(format!(
+                "Framegroup '{}' of appearance '{}' needs a `spritesheet` for animations without `sprite_ids`",
+                framegroup.name, appearance.name
+            ))
+            .in_field("spritesheet"));
+        };
+
+        resolver
+            .resolve_for(appearance, framegroup, spritesheet)
+            .map_err(|error| error.in_field("spritesheet"))
+    };
+
+    // Modo rotação: gera as 8 direções a partir de uma única animação modelo
+    if let Some(mode) = framegroup.rotation {
+        let animation = framegroup
+            .animations
+            .values()
+            .next()
+            .expect("framegroup_problems garante uma única animação");
+
+        let directions = generate_rotated_directions_rgba(
+            &resolve_spritesheet()?,
+            cell_width,
+            cell_height,
+            animation.frame_count,
+            framegroup.orientation,
+            mode,
+        )
+        .map_err(|error| error.in_field("spritesheet"))?;
+
+        writer.write_u32::<LittleEndian>(directions.len() as u32)?;
+
+        for (direction, sprites) in directions {
+            if let Some(first_frame) = first_frame.as_deref_mut() {
+                track_first_frame(first_frame, Some(direction), &sprites);
             }
+
+            let sprite_ids = sprite_writer.save(&sprites);
+            write_animation(writer, Some(direction), &sprite_ids, animation)
+                .map_err(|error| error.in_direction(Some(direction)))?;
         }
+
+        return Ok(());
     }
 
-    // Salva o arquivo .dat
-    let dat_path = output_path.join("appearances.dat");
-    let dat_bytes = dat_buffer.into_inner();
-    fs::write(&dat_path, &dat_bytes)?;
+    // Número de animações (direções)
+    writer.write_u32::<LittleEndian>(framegroup.animations.len() as u32)?;
 
-    Ok(CompilationResult {
-        appearances_count: appearances_file.appearances.len(),
-        sprites_count: total_sprites,
-        dat_size: dat_bytes.len(),
-        total_spr_size,
-    })
+    // Processa cada animação/direção
+    for (direction, animation) in &framegroup.animations {
+        // Animações com `sprite_ids` usam sprites já existentes
+        if let Some(sprite_ids) = &animation.sprite_ids {
+            sprite_writer
+                .reuse(sprite_ids)
+                .map_err(|error| error.in_field("sprite_ids").in_direction(*direction))?;
+
+            // Só o primeiro frame é lido, e apenas quando a cor do minimapa é calculada
+            if let Some(first_frame) = first_frame.as_deref_mut()
+                && sprite_writer.options.minimap_color != MinimapColorMode::Off
+                && let Some(&sprite_id) = sprite_ids.first()
+            {
+                let sprite = sprite_writer
+                    .load_reused(sprite_id)
+                    .map_err(|error| error.in_field("sprite_ids").in_direction(*direction))?;
+                track_first_frame(first_frame, *direction, &[sprite]);
+            }
+
+            write_animation(writer, *direction, sprite_ids, animation)
+                .map_err(|error| error.in_direction(*direction))?;
+            continue;
+        }
+
+        // Determina o número de direções para recorte
+        let num_directions = if direction.is_some() {
+            // Se há uma direção específica, assumimos que o spritesheet
+            // contém todas as direções recortadas (sem `sprite_ids`) em linhas
+            sliced_directions(&framegroup.animations).len() as u32
+        } else {
+            0
+        };
+
+        // Recorta o spritesheet em sprites individuais
+        let sprites = slice_spritesheet_rgba(
+            &resolve_spritesheet()?,
+            cell_width,
+            cell_height,
+            animation.frame_count,
+            num_directions,
+            framegroup.orientation,
+        )
+        .map_err(|error| error.in_field("spritesheet"))?;
+
+        // Determina quais sprites pertencem a esta animação específica
+        let sprites: Vec<RgbaSprite> = if let Some(dir) = direction {
+            // Calcula os índices baseado na direção e orientação
+            let direction_index = sliced_directions(&framegroup.animations)
+                .iter()
+                .position(|d| d == dir)
+                .unwrap_or(0);
+
+            // Para orientação Horizontal: sprites são organizadas por frame
+            // Frame 0: [N][E][S][W], Frame 1: [N][E][S][W], etc.
+            // Para orientação Vertical: sprites são organizadas por direção
+            // North: [N1][N2][N3]..., East: [E1][E2][E3]..., etc.
+            let sprite_indices: Vec<usize> = match framegroup.orientation {
+                crate::types::Orientation::Horizontal => {
+                    // Para cada frame, pega a sprite na coluna da direção
+                    (0..animation.frame_count)
+                        .map(|frame| (frame as usize * num_directions as usize) + direction_index)
+                        .collect()
+                }
+                crate::types::Orientation::Vertical => {
+                    // Pega todas as sprites da linha da direção
+                    let start_idx = direction_index * animation.frame_count as usize;
+                    (start_idx..start_idx + animation.frame_count as usize).collect()
+                }
+            };
+
+            sprite_indices.iter().map(|&idx| sprites[idx].clone()).collect()
+        } else {
+            // Sem direção, todas as sprites são dessa animação
+            sprites
+        };
+
+        if let Some(first_frame) = first_frame.as_deref_mut() {
+            track_first_frame(first_frame, *direction, &sprites);
+        }
+
+        // Salva as sprites
+        let sprite_ids = sprite_writer.save(&sprites);
+
+        write_animation(writer, *direction, &sprite_ids, animation).map_err(|error| error.in_direction(*direction))?;
+    }
+
+    Ok(())
+}
+
+/// Escreve uma animação (direção, sprite IDs e temporização) no .dat
+fn write_animation<W: Write>(
+    writer: &mut W, direction: Option<Direction>, sprite_ids: &[u32], animation: &Animation,
+) -> Result<()> {
+    check::first(check::animation_problems(animation))?;
+
+    // Escreve a direção (ou None se não houver)
+    if let Some(dir) = direction {
+        writer.write_u8(1)?; // Tem direção
+        writer.write_u8(direction_to_u8(dir))?;
+    } else {
+        writer.write_u8(0)?; // Sem direção
+    }
+
+    // Escreve o número de sprite IDs
+    writer.write_u32::<LittleEndian>(sprite_ids.len() as u32)?;
+
+    // Escreve cada sprite ID
+    for sprite_id in sprite_ids {
+        writer.write_u32::<LittleEndian>(*sprite_id)?;
+    }
+
+    // Escreve a duração total de um ciclo (soma das durações mínimas)
+    let timings = frame_timings(animation, sprite_ids.len());
+    let total = timings.iter().fold(0u32, |total, &(min, _)| total.saturating_add(min));
+    writer.write_u32::<LittleEndian>(total)?;
+
+    // Escreve a temporização de cada frame (min, max)
+    writer.write_u32::<LittleEndian>(timings.len() as u32)?;
+    for (min, max) in timings {
+        writer.write_u32::<LittleEndian>(min)?;
+        writer.write_u32::<LittleEndian>(max)?;
+    }
+
+    // Escreve o flag looped (1 = true, 0 = false)
+    let playback = animation.playback_mode();
+    writer.write_u8(if playback.is_looping() { 1 } else { 0 })?;
+
+    // Escreve o modo de reprodução e o número de ciclos (0 = infinito)
+    writer.write_u8(playback_to_u8(playback))?;
+    writer.write_u32::<LittleEndian>(animation.loop_count.unwrap_or(0))?;
+
+    // Escreve a política de frame inicial (tipo + índice)
+    let (start_kind, start_index) = match animation.start_frame {
+        StartFrame::First => (0, 0),
+        StartFrame::Random => (1, 0),
+        StartFrame::Frame(index) => (2, index),
+    };
+    writer.write_u8(start_kind)?;
+    writer.write_u32::<LittleEndian>(start_index)?;
+
+    // Escreve o flag synchronized (1 = relógio global, 0 = por instância)
+    writer.write_u8(if animation.synchronized { 1 } else { 0 })?;
+
+    // Escreve os eventos de frame
+    writer.write_u32::<LittleEndian>(animation.events.len() as u32)?;
+    for event in &animation.events {
+        writer.write_u32::<LittleEndian>(event.frame)?;
+        write_string(writer, &event.name)?;
+        write_optional_string(writer, event.payload.as_deref())?;
+    }
+
+    // Escreve os pontos de ancoragem (ordenados por nome)
+    let mut attachments: Vec<_> = animation.attachments.iter().collect();
+    attachments.sort_by_key(|(name, _)| *name);
+
+    writer.write_u32::<LittleEndian>(attachments.len() as u32)?;
+    for (name, attachment) in attachments {
+        let points = match attachment {
+            AttachmentPoint::Fixed(point) => std::slice::from_ref(point),
+            AttachmentPoint::PerFrame(points) => points.as_slice(),
+        };
+
+        write_string(writer, name)?;
+        writer.write_u32::<LittleEndian>(points.len() as u32)?;
+        for point in points {
+            writer.write_i32::<LittleEndian>(point.x)?;
+            writer.write_i32::<LittleEndian>(point.y)?;
+        }
+    }
+
+    // Escreve as formas de colisão da direção
+    write_shapes(writer, &animation.shapes)?;
+
+    // Metadata livre
+    write_metadata(writer, &animation.metadata)?;
+
+    Ok(())
+}
+
+/// Escreve uma lista de formas de colisão
+fn write_shapes<W: Write>(writer: &mut W, shapes: &[CollisionShape]) -> Result<()> {
+    writer.write_u32::<LittleEndian>(shapes.len() as u32)?;
+    for shape in shapes {
+        writer.write_u8(match shape.kind {
+            ShapeKind::Hitbox => 0,
+            ShapeKind::Hurtbox => 1,
+        })?;
+        writer.write_i32::<LittleEndian>(shape.rect.x)?;
+        writer.write_i32::<LittleEndian>(shape.rect.y)?;
+        writer.write_u32::<LittleEndian>(shape.rect.width)?;
+        writer.write_u32::<LittleEndian>(shape.rect.height)?;
+        // Frame (u32::MAX = todos os frames)
+        writer.write_u32::<LittleEndian>(shape.frame.unwrap_or(u32::MAX))?;
+    }
+    Ok(())
+}
+
+/// Resolve a temporização (min, max) de cada frame de uma animação
+///
+/// Sem `frame_durations`, todos os frames usam `frame_duration`, ou
+/// `duration` dividida igualmente entre eles (o resto vai para os primeiros
+/// frames, então a soma é exatamente `duration`). A quantidade e os
+/// intervalos já foram verificados em `animation_problems`.
+fn frame_timings(animation: &Animation, frame_count: usize) -> Vec<(u32, u32)> {
+    if let Some(frame_durations) = &animation.frame_durations {
+        return frame_durations.iter().map(FrameDuration::range).collect();
+    }
+
+    if let Some(duration) = animation.frame_duration {
+        return vec![(duration, duration); frame_count];
+    }
+
+    let total = animation.duration.unwrap_or(0);
+    let frames = frame_count.max(1) as u32;
+    (0..frames)
+        .take(frame_count)
+        .map(|frame| {
+            let duration = total / frames + u32::from(frame < total % frames);
+            (duration, duration)
+        })
+        .collect()
 }
 
 /// Converte Direction para u8
@@ -193,14 +652,69 @@ fn direction_to_u8(dir: Direction) -> u8 {
     }
 }
 
-/// Calcula o índice da linha para uma direção específica
-fn calculate_direction_row(dir: Direction, animations: &HashMap<Option<Direction>, crate::types::Animation>) -> usize {
+/// Escreve categoria (u8), flags (u32) e ground_speed opcional (u8 flag + u32)
+fn write_properties<W: Write>(writer: &mut W, appearance: &Appearance) -> Result<()> {
+    let properties = &appearance.properties;
+
+    writer.write_u8(category_to_u8(appearance.category))?;
+
+    let mut flags = 0u32;
+    if properties.blocking {
+        flags |= Properties::BLOCKING;
+    }
+    if properties.pickupable {
+        flags |= Properties::PICKUPABLE;
+    }
+    if properties.stackable {
+        flags |= Properties::STACKABLE;
+    }
+    writer.write_u32::<LittleEndian>(flags)?;
+
+    match properties.ground_speed {
+        Some(speed) => {
+            writer.write_u8(1)?;
+            writer.write_u32::<LittleEndian>(speed)?;
+        }
+        None => writer.write_u8(0)?,
+    }
+
+    Ok(())
+}
+
+/// Converte Category para u8 (0 = sem categoria)
+fn category_to_u8(category: Option<Category>) -> u8 {
+    match category {
+        None => 0,
+        Some(Category::Item) => 1,
+        Some(Category::Ground) => 2,
+        Some(Category::Creature) => 3,
+        Some(Category::Effect) => 4,
+        Some(Category::Missile) => 5,
+    }
+}
+
+/// Converte PlaybackMode para u8
+fn playback_to_u8(mode: PlaybackMode) -> u8 {
+    match mode {
+        PlaybackMode::Loop => 0,
+        PlaybackMode::Once => 1,
+        PlaybackMode::OnceAndHold => 2,
+        PlaybackMode::PingPong => 3,
+    }
+}
+
+/// Direções recortadas do spritesheet (sem `sprite_ids`), na ordem das linhas
+pub(crate) fn sliced_directions(animations: &HashMap<Option<Direction>, Animation>) -> Vec<Direction> {
     // Ordena as direções para garantir consistência
-    let mut directions: Vec<Direction> = animations.keys().filter_map(|k| *k).collect();
+    let mut directions: Vec<Direction> = animations
+        .iter()
+        .filter(|(_, animation)| animation.sprite_ids.is_none())
+        .filter_map(|(direction, _)| *direction)
+        .collect();
 
     directions.sort_by_key(|d| direction_to_u8(*d));
 
-    directions.iter().position(|d| *d == dir).unwrap_or(0)
+    directions
 }
 
 /// Salva um arquivo .spr
@@ -224,9 +738,473 @@ fn save_sprite_file<P: AsRef<Path>>(output_path: P, sprite_id: u32, sprite_data:
     Ok(())
 }
 
+/// Caminho do .spr de um ID de `sprite_ids`, que precisa existir na pasta de sprites
+fn reused_sprite_path(sprites_path: &Path, sprite_id: u32) -> Result<PathBuf> {
+    let filepath = sprites_path.join(format!("{:05}.spr", sprite_id));
+
+    if !filepath.is_file() {
+        return Err(AppearanceError::SpriteNotFound {
+            path: filepath.display().to_string(),
+        });
+    }
+
+    Ok(filepath)
+}
+
+/// Coloca um .spr pré-existente no output: hard link quando possível
+/// (mesmo sistema de arquivos), cópia caso contrário
+fn link_sprite_file(source: &Path, target: &Path) -> Result<()> {
+    if target.exists() {
+        fs::remove_file(target)?;
+    }
+
+    if fs::hard_link(source, target).is_err() {
+        fs::copy(source, target)?;
+    }
+
+    Ok(())
+}
+
+/// Lê um arquivo .spr pré-existente (usado por `sprite_ids`)
+fn load_sprite_file(filepath: &Path) -> Result<RgbaSprite> {
+    let mut cursor = Cursor::new(fs::read(filepath)?);
+
+    // Header do .spr
+    let width = cursor.read_u32::<LittleEndian>()?;
+    let height = cursor.read_u32::<LittleEndian>()?;
+    let compressed_size = cursor.read_u32::<LittleEndian>()?;
+
+    // O tamanho vem do arquivo: confere antes de alocar
+    let remaining = cursor.get_ref().len() as u64 - cursor.position();
+    if u64::from(compressed_size) > remaining {
+        return Err(AppearanceError::InvalidData(format!(
+            "Sprite file '{}' is truncated: header declares {} bytes of pixels but only {} remain",
+            filepath.display(),
+            compressed_size,
+            remaining
+        )));
+    }
+
+    // Pixels compactados
+    let mut compressed_pixels = vec![0u8; compressed_size as usize];
+    cursor.read_exact(&mut compressed_pixels)?;
+
+    let mut pixels = Vec::new();
+    GzDecoder::new(&compressed_pixels[..]).read_to_end(&mut pixels)?;
+
+    Ok(RgbaSprite {
+        data: SpriteData {
+            width,
+            height,
+            compressed_pixels,
+        },
+        pixels,
+    })
+}
+
+/// IDs informados em `sprite_ids` em todo o arquivo
+fn reserved_sprite_ids(appearances_file: &AppearancesFile) -> HashSet<u32> {
+    appearances_file
+        .appearances
+        .iter()
+        .flat_map(|appearance| &appearance.framegroups)
+        .flat_map(|framegroup| framegroup.animations.values())
+        .filter_map(|animation| animation.sprite_ids.as_ref())
+        .flatten()
+        .copied()
+        .collect()
+}
+
+/// Salva o arquivo masks.dat: count (u32) + [sprite_id (u32) + máscara]
+fn save_masks_file(output_path: &Path, masks: &[(u32, SpriteMask)]) -> Result<()> {
+    let mut buffer = Cursor::new(Vec::new());
+
+    buffer.write_u32::<LittleEndian>(masks.len() as u32)?;
+    for (sprite_id, mask) in masks {
+        buffer.write_u32::<LittleEndian>(*sprite_id)?;
+        mask.write(&mut buffer)?;
+    }
+
+    fs::write(output_path.join("masks.dat"), buffer.into_inner())?;
+    Ok(())
+}
+
 /// Escreve uma string no formato: length (u32) + bytes (UTF-8)
 fn write_string<W: Write>(writer: &mut W, s: &str) -> Result<()> {
     writer.write_u32::<LittleEndian>(s.len() as u32)?;
     writer.write_all(s.as_bytes())?;
     Ok(())
 }
+
+/// Guarda o primeiro frame da direção de menor ordem (sem direção vem antes)
+fn track_first_frame(first_frame: &mut Option<(u8, RgbaSprite)>, direction: Option<Direction>, sprites: &[RgbaSprite]) {
+    let order = direction.map_or(0, |dir| direction_to_u8(dir) + 1);
+
+    if let Some(sprite) = sprites.first()
+        && first_frame.as_ref().is_none_or(|(current, _)| order < *current)
+    {
+        *first_frame = Some((order, sprite.clone()));
+    }
+}
+
+/// Escreve uma luz opcional: flag (u8) + intensity (f32), cor (3 × u8),
+/// radius (u32) e flicker opcional (u8 flag + period u32 + amount f32)
+fn write_light<W: Write>(writer: &mut W, light: Option<&Light>) -> Result<()> {
+    let Some(light) = light else {
+        writer.write_u8(0)?;
+        return Ok(());
+    };
+
+    writer.write_u8(1)?;
+    writer.write_f32::<LittleEndian>(light.intensity)?;
+    writer.write_all(&[light.color.r, light.color.g, light.color.b])?;
+    writer.write_u32::<LittleEndian>(light.radius)?;
+
+    match light.flicker {
+        Some(flicker) => {
+            writer.write_u8(1)?;
+            writer.write_u32::<LittleEndian>(flicker.period)?;
+            writer.write_f32::<LittleEndian>(flicker.amount)?;
+        }
+        None => writer.write_u8(0)?,
+    }
+
+    Ok(())
+}
+
+/// Escreve a luz de um framegroup: como `write_light`, com flag 2 = luz apagada
+fn write_framegroup_light<W: Write>(writer: &mut W, light: Option<&FrameGroupLight>) -> Result<()> {
+    match light {
+        Some(FrameGroupLight::Light(light)) => write_light(writer, Some(light)),
+        Some(FrameGroupLight::Disabled(_)) => {
+            writer.write_u8(2)?;
+            Ok(())
+        }
+        None => write_light(writer, None),
+    }
+}
+
+/// Escreve a metadata como JSON compacto (string opcional, ausente se vazia)
+fn write_metadata<W: Write>(writer: &mut W, metadata: &Metadata) -> Result<()> {
+    if metadata.is_empty() {
+        return write_optional_string(writer, None);
+    }
+
+    write_optional_string(writer, Some(&serde_json::to_string(metadata)?))
+}
+
+/// Escreve uma string opcional: flag (u8) + string se presente
+fn write_optional_string<W: Write>(writer: &mut W, s: Option<&str>) -> Result<()> {
+    match s {
+        Some(s) => {
+            writer.write_u8(1)?;
+            write_string(writer, s)
+        }
+        None => {
+            writer.write_u8(0)?;
+            Ok(())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::TempDir;
+
+    #[test]
+    fn test_duplicate_names_are_rejected() {
+        let file: AppearancesFile = serde_json::from_str(
+            r#"{
+                "version": 2,
+                "appearances": [
+                    { "id": 1, "name": "wolf", "size": 32, "framegroups": [] },
+                    { "id": 7, "name": "wolf", "size": 32, "framegroups": [] }
+                ]
+            }"#,
+        )
+        .unwrap();
+
+        let result = compile_appearances(&file, Path::new("."), Path::new("unused"));
+        assert!(matches!(
+            result,
+            Err(AppearanceError::DuplicateName { ref name, first_id: 1, second_id: 7 }) if name == "wolf"
+        ));
+    }
+
+    #[test]
+    fn test_duration_is_split_across_frames() {
+        let animation = |json: serde_json::Value| serde_json::from_value::<Animation>(json).unwrap();
+
+        // `duration` é o total do ciclo; o resto da divisão vai para os primeiros frames
+        let total = animation(serde_json::json!({ "frame_count": 3, "duration": 200 }));
+        assert_eq!(frame_timings(&total, 3), [(67, 67), (67, 67), (66, 66)]);
+        assert!(check::animation_problems(&total).is_empty());
+
+        let per_frame = animation(serde_json::json!({ "frame_count": 3, "frame_duration": 200 }));
+        assert_eq!(frame_timings(&per_frame, 3), [(200, 200); 3]);
+
+        let both = animation(serde_json::json!({ "frame_count": 3, "duration": 600, "frame_duration": 200 }));
+        assert_eq!(check::animation_problems(&both)[0].field, "frame_duration");
+
+        let fixed = animation(serde_json::json!({ "frame_count": 2, "duration": 100, "frame_durations": [30, 90] }));
+        assert_eq!(frame_timings(&fixed, 2), [(30, 30), (90, 90)]);
+        assert_eq!(frame_timings(&animation(serde_json::json!({ "frame_count": 2 })), 2), [(0, 0); 2]);
+    }
+
+    #[test]
+    fn test_duplicate_ids_are_rejected() {
+        let file: AppearancesFile = serde_json::from_str(
+            r#"{
+                "version": 2,
+                "appearances": [
+                    { "id": 4, "name": "wolf", "size": 32, "framegroups": [] },
+                    { "id": 4, "name": "bear", "size": 32, "framegroups": [] }
+                ]
+            }"#,
+        )
+        .unwrap();
+
+        let result = compile_appearances(&file, Path::new("."), Path::new("unused"));
+        assert!(matches!(
+            result,
+            Err(AppearanceError::DuplicateId { id: 4, ref first, ref second }) if first == "wolf" && second == "bear"
+        ));
+    }
+
+    #[test]
+    fn test_empty_and_mixed_animations_are_rejected() {
+        let root = TempDir::new("framegroup-animations");
+        let compile = |animations: serde_json::Value| {
+            let file: AppearancesFile = serde_json::from_value(serde_json::json!({
+                "version": 2,
+                "appearances": [{
+                    "id": 1, "name": "orc", "size": 32,
+                    "framegroups": [{ "name": "idle", "spritesheet": "idle.png", "animations": animations }]
+                }]
+            }))
+            .unwrap();
+            compile_appearances(&file, root.path(), &root.join("compiled")).err().unwrap()
+        };
+
+        let error = compile(serde_json::json!({}));
+        assert_eq!(error.root().to_string(), "Invalid appearance data: Framegroup has no animations");
+        assert_eq!(
+            error.context().unwrap().path.as_deref(),
+            Some("appearances[0].framegroups[0].animations")
+        );
+
+        let error = compile(serde_json::json!({ "null": { "frame_count": 1 }, "north": { "frame_count": 1 } }));
+        assert!(error.root().to_string().contains("mixes directional animations"));
+        assert_eq!(
+            error.context().unwrap().path.as_deref(),
+            Some("appearances[0].framegroups[0].animations")
+        );
+    }
+
+    #[test]
+    fn test_rotation_rejects_shapes_and_attachments() {
+        let file: AppearancesFile = serde_json::from_value(serde_json::json!({
+            "version": 2,
+            "appearances": [{
+                "id": 1, "name": "arrow", "size": 32,
+                "framegroups": [{
+                    "name": "fly", "spritesheet": "arrow.png", "rotation": "step45",
+                    "animations": { "null": { "attachments": { "tip": { "x": 16, "y": 0 } } } }
+                }]
+            }]
+        }))
+        .unwrap();
+
+        let root = TempDir::new("rotation-anchors");
+        let error = compile_appearances(&file, root.path(), &root.join("compiled"))
+            .err()
+            .unwrap();
+        assert!(
+            error
+                .root()
+                .to_string()
+                .contains("cannot define `shapes` or `attachments`")
+        );
+        assert_eq!(
+            error.context().unwrap().path.as_deref(),
+            Some("appearances[0].framegroups[0].animations")
+        );
+    }
+
+    #[test]
+    fn test_light_rejects_non_finite_values_and_enabled_markers() {
+        let mut file: AppearancesFile = serde_json::from_value(serde_json::json!({
+            "version": 2,
+            "appearances": [{
+                "id": 1, "name": "torch", "size": 32,
+                "light": { "intensity": 1.0, "color": "#ff8800", "radius": 4 },
+                "framegroups": [{
+                    "name": "lit", "spritesheet": "torch.png",
+                    "light": { "enabled": true },
+                    "animations": { "null": { "frame_count": 1 } }
+                }]
+            }]
+        }))
+        .unwrap();
+
+        let root = TempDir::new("light-values");
+        let error = compile_appearances(&file, root.path(), &root.join("compiled"))
+            .err()
+            .unwrap();
+        assert!(error.root().to_string().contains("`enabled: true` is not a light"));
+        assert_eq!(
+            error.context().unwrap().path.as_deref(),
+            Some("appearances[0].framegroups[0].light")
+        );
+
+        file.appearances[0].framegroups[0].light = None;
+        file.appearances[0].light.as_mut().unwrap().intensity = f32::INFINITY;
+        let error = compile_appearances(&file, root.path(), &root.join("compiled"))
+            .err()
+            .unwrap();
+        assert!(error.root().to_string().contains("must be a finite number"));
+    }
+
+    #[test]
+    fn test_loop_count_rejects_zero_and_non_looping_playback() {
+        let mut file: AppearancesFile = serde_json::from_value(serde_json::json!({
+            "version": 2,
+            "appearances": [{
+                "id": 1, "name": "door", "size": 32,
+                "framegroups": [{
+                    "name": "open", "spritesheet": "door.png",
+                    "animations": { "null": { "frame_count": 2, "loop_count": 0 } }
+                }]
+            }]
+        }))
+        .unwrap();
+
+        let root = TempDir::new("loop-count");
+        image::RgbaImage::new(64, 32).save(root.join("door.png")).unwrap();
+        let error = compile_appearances(&file, root.path(), &root.join("compiled"))
+            .err()
+            .unwrap();
+        assert!(error.root().to_string().contains("`loop_count` must be greater than zero"));
+        assert_eq!(
+            error.context().unwrap().path.as_deref(),
+            Some("appearances[0].framegroups[0].animations.null.loop_count")
+        );
+
+        let animation = file.appearances[0].framegroups[0].animations.get_mut(&None).unwrap();
+        animation.loop_count = Some(2);
+        animation.playback = Some(PlaybackMode::OnceAndHold);
+        let error = compile_appearances(&file, root.path(), &root.join("compiled"))
+            .err()
+            .unwrap();
+        assert!(
+            error
+                .root()
+                .to_string()
+                .contains("only allowed with `loop` or `ping_pong` playback")
+        );
+    }
+
+    #[test]
+    fn test_sprite_ids_reuse_existing_sprites() {
+        let root = TempDir::new("sprite-ids");
+        let sprites = root.join("legacy");
+        let output = root.join("compiled");
+        image::RgbaImage::new(8, 4).save(root.join("walk.png")).unwrap();
+
+        let sheet = root.join("walk.png");
+        let compile = |output: &Path, appearances: serde_json::Value| {
+            let file: AppearancesFile =
+                serde_json::from_value(serde_json::json!({ "version": 2, "appearances": appearances })).unwrap();
+            let options = CompileOptions {
+                sprites_path: Some(sprites.clone()),
+                alpha_masks: true,
+                ..Default::default()
+            };
+            compile_appearances_with_options(&file, root.path(), output, &options)
+        };
+
+        // Uma compilação anterior gera 00001.spr e 00002.spr na pasta de sprites
+        compile(
+            &sprites,
+            serde_json::json!([{
+                "id": 1, "name": "legacy", "size": 4,
+                "framegroups": [{ "name": "idle", "spritesheet": sheet, "animations": { "null": { "frame_count": 2 } } }]
+            }]),
+        )
+        .unwrap();
+
+        // Reusa o sprite 2 em um output vazio; as sprites novas pulam o ID reservado
+        let result = compile(
+            &output,
+            serde_json::json!([
+                {
+                    "id": 1, "name": "reused", "size": 4,
+                    "framegroups": [{ "name": "idle", "animations": { "null": { "sprite_ids": [2, 2] } } }]
+                },
+                {
+                    "id": 2, "name": "fresh", "size": 4,
+                    "framegroups": [{ "name": "idle", "spritesheet": sheet, "animations": { "null": { "frame_count": 2 } } }]
+                }
+            ]),
+        )
+        .unwrap();
+        assert_eq!(result.sprites_count, 2);
+        assert_eq!(result.reused_sprites_count, 1);
+
+        // A sprite reaproveitada é ligada ao output sem ser regravada e entra no masks.dat
+        assert_eq!(
+            fs::read(output.join("00002.spr")).unwrap(),
+            fs::read(sprites.join("00002.spr")).unwrap()
+        );
+        let (database, mut loader) = crate::load_all(&output).unwrap();
+        assert_eq!(loader.load_sprite(2).unwrap().width, 4);
+        assert!(loader.get_mask(2).is_ok());
+
+        let sprite_ids = |name: &str| {
+            database.get_by_name(name).unwrap().framegroups[0].animations[&None]
+                .sprite_ids
+                .clone()
+        };
+        assert_eq!(sprite_ids("reused"), [2, 2]);
+        assert_eq!(sprite_ids("fresh"), [1, 3]);
+
+        // Sem `sprites_path` os IDs são procurados no próprio output
+        let file: AppearancesFile = serde_json::from_value(serde_json::json!({
+            "version": 2,
+            "appearances": [{
+                "id": 1, "name": "kept", "size": 4,
+                "framegroups": [{ "name": "idle", "animations": { "null": { "sprite_ids": [3] } } }]
+            }]
+        }))
+        .unwrap();
+        assert_eq!(compile_appearances(&file, root.path(), &output).unwrap().reused_sprites_count, 1);
+
+        // IDs sem .spr na pasta de sprites são rejeitados, mesmo que existam no output
+        let broken = |sprite_id: u32| {
+            compile(
+                &output,
+                serde_json::json!([{
+                    "id": 1, "name": "broken", "size": 4,
+                    "framegroups": [{ "name": "idle", "animations": { "null": { "sprite_ids": [sprite_id] } } }]
+                }]),
+            )
+            .err()
+            .unwrap()
+        };
+        let error = broken(3);
+        assert!(matches!(error.root(), AppearanceError::SpriteNotFound { .. }));
+        assert_eq!(
+            error.context().unwrap().path.as_deref(),
+            Some("appearances[0].framegroups[0].animations.null.sprite_ids")
+        );
+
+        // Um .spr truncado é rejeitado antes de alocar o tamanho declarado
+        let mut truncated = Vec::new();
+        for value in [4u32, 4, u32::MAX] {
+            truncated.extend(value.to_le_bytes());
+        }
+        fs::write(sprites.join("00009.spr"), truncated).unwrap();
+        assert!(broken(9).root().to_string().contains("truncated"));
+    }
+}
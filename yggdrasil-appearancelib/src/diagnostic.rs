@@ -0,0 +1,477 @@
+use crate::types::{Appearance, Direction, direction_key};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Posição em um arquivo de appearances (linha e coluna começam em 1)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SourceLocation {
+    pub file:   PathBuf,
+    pub line:   usize,
+    pub column: usize,
+}
+
+impl SourceLocation {
+    /// Trecho do arquivo ao redor da posição, com um marcador na coluna
+    ///
+    /// Retorna None se o arquivo não puder ser lido ou a linha não existir.
+    pub fn snippet(&self) -> Option<String> {
+        let text = fs::read_to_string(&self.file).ok()?;
+        let lines: Vec<&str> = text.lines().collect();
+        let line = lines.get(self.line.checked_sub(1)?)?;
+
+        let first = self.line.saturating_sub(2).max(1);
+        let width = self.line.to_string().len();
+
+        let mut snippet = format!("{:width$}--> {}\n", "", self, width = width);
+        snippet.push_str(&format!("{:width$} |\n", "", width = width));
+        for number in first..self.line {
+            snippet.push_str(&format!("{:>width$} | {}\n", number, lines[number - 1], width = width));
+        }
+        snippet.push_str(&format!("{:>width$} | {}\n", self.line, line, width = width));
+        snippet.push_str(&format!(
+            "{:width$} | {}^",
+            "",
+            " ".repeat(self.column.saturating_sub(1)),
+            width = width
+        ));
+
+        Some(snippet)
+    }
+}
+
+impl fmt::Display for SourceLocation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}:{}", self.file.display(), self.line, self.column)
+    }
+}
+
+/// Onde um erro aconteceu: appearance, framegroup, direção, campo e posição no arquivo
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ErrorContext {
+    pub appearance_id:   Option<u32>,
+    pub appearance_name: Option<String>,
+    pub framegroup:      Option<String>,
+    /// Chave da direção no mapa de animações (`"north"`, `"null"`, ...)
+    pub direction:       Option<String>,
+    /// Campo com problema (ex.: `frame_count`, `spritesheet`)
+    pub field:           Option<String>,
+    /// Caminho do campo no arquivo mesclado (ex.: `appearances[3].framegroups[0].animations.north`)
+    pub path:            Option<String>,
+    /// Posição aproximada no arquivo de origem, só para arquivos JSON: o item
+    /// mais interno de `path` que existe no arquivo (um campo herdado de um
+    /// template aponta para a appearance). Erros de sintaxe trazem a posição
+    /// informada pelo parser em todos os formatos.
+    pub location:        Option<SourceLocation>,
+}
+
+impl ErrorContext {
+    /// Marca o campo com problema (ex.: `spritesheet`)
+    ///
+    /// Os métodos `in_*` são chamados de dentro para fora (campo, direção,
+    /// framegroup, appearance), montando o caminho a partir do fim.
+    pub(crate) fn in_field(&mut self, field: &str) {
+        self.field = Some(field.to_string());
+        self.prefix_path(field.to_string());
+    }
+
+    /// Marca a direção da animação
+    pub(crate) fn in_direction(&mut self, direction: Option<Direction>) {
+        let key = direction_key(direction);
+        self.prefix_path(format!("animations.{}", key));
+        self.direction = Some(key);
+    }
+
+    /// Marca o framegroup
+    pub(crate) fn in_framegroup(&mut self, index: usize, name: &str) {
+        self.framegroup = Some(name.to_string());
+        self.prefix_path(format!("framegroups[{}]", index));
+    }
+
+    /// Marca a appearance e localiza o contexto no arquivo de origem
+    pub(crate) fn in_appearance(&mut self, index: usize, appearance: &Appearance) {
+        self.appearance_id = Some(appearance.id);
+        self.appearance_name = Some(appearance.name.clone());
+        self.prefix_path(format!("appearances[{}]", index));
+        if let Some(source) = &appearance.source {
+            self.locate_from(source.clone());
+        }
+    }
+
+    /// Acrescenta um segmento no início do caminho
+    fn prefix_path(&mut self, segment: String) {
+        self.path = Some(match self.path.take() {
+            Some(path) => format!("{}.{}", segment, path),
+            None => segment,
+        });
+    }
+
+    /// Localiza o caminho no arquivo JSON da appearance que começa em `start`
+    ///
+    /// Os índices da appearance e do framegroup (buscado pelo `name`, já que
+    /// templates e includes mudam a ordem) são os do arquivo de origem. Se o
+    /// arquivo não puder ser lido, a posição fica em `start`.
+    pub(crate) fn locate_from(&mut self, start: SourceLocation) {
+        let location = self
+            .path
+            .as_deref()
+            .and_then(|path| locate_in_appearance(&start, path, self.framegroup.as_deref()));
+        self.location = Some(location.unwrap_or(start));
+    }
+}
+
+/// Posição de `path` (relativo ao arquivo mesclado) no arquivo JSON da appearance em `start`
+fn locate_in_appearance(start: &SourceLocation, path: &str, framegroup: Option<&str>) -> Option<SourceLocation> {
+    let text = fs::read_to_string(&start.file).ok()?;
+    let spans = json_spans(&text)?;
+    let value: Value = serde_json::from_str(&text).ok()?;
+
+    // Índice da appearance no arquivo de origem
+    let offset = offset_of(&text, start.line, start.column);
+    let appearances = value["appearances"].as_array()?;
+    let index = (0..appearances.len()).find(|index| spans.get(&format!("appearances[{}]", index)) == Some(&offset))?;
+    let mut path_in_file = format!("appearances[{}]", index);
+
+    // O resto do caminho, com o framegroup reindexado pelo nome
+    let rest = path.split_once('.').map_or("", |(_, rest)| rest);
+    match (rest.strip_prefix("framegroups["), framegroup) {
+        (Some(rest), Some(name)) => {
+            let rest = rest.split_once(']').map_or("", |(_, rest)| rest);
+            let framegroups = appearances[index]["framegroups"].as_array();
+            if let Some(position) = framegroups
+                .into_iter()
+                .flatten()
+                .position(|framegroup| framegroup["name"] == name)
+            {
+                path_in_file.push_str(&format!(".framegroups[{}]{}", position, rest));
+            }
+        }
+        (Some(_), None) => {}
+        (None, _) if !rest.is_empty() => path_in_file.push_str(&format!(".{}", rest)),
+        (None, _) => {}
+    }
+
+    locate_path(&start.file, &text, &spans, &path_in_file)
+}
+
+/// Posição de um caminho em um arquivo JSON (sem includes antes dos índices
+/// de `appearances`), ou None para outros formatos
+pub(crate) fn locate_in_file(file: &Path, path: &str) -> Option<SourceLocation> {
+    let text = fs::read_to_string(file).ok()?;
+    let spans = json_spans(&text)?;
+
+    // Com includes, o índice no arquivo mesclado não é o do arquivo principal
+    let path = if spans.contains_key("include") && path.starts_with("appearances[") {
+        "appearances"
+    } else {
+        path
+    };
+
+    locate_path(file, &text, &spans, path)
+}
+
+/// Posição do item mais interno do caminho que existe no arquivo
+fn locate_path(file: &Path, text: &str, spans: &HashMap<String, usize>, path: &str) -> Option<SourceLocation> {
+    let mut path = path;
+    let offset = loop {
+        if let Some(&offset) = spans.get(path) {
+            break offset;
+        }
+        path = &path[..path.rfind(['.', '['])?];
+    };
+
+    let (line, column) = line_column(text, offset);
+    Some(SourceLocation {
+        file: file.to_path_buf(),
+        line,
+        column,
+    })
+}
+
+impl fmt::Display for ErrorContext {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut parts = Vec::new();
+
+        match (self.appearance_id, &self.appearance_name) {
+            (Some(id), Some(name)) => parts.push(format!("appearance {} '{}'", id, name)),
+            (Some(id), None) => parts.push(format!("appearance {}", id)),
+            (None, Some(name)) => parts.push(format!("appearance '{}'", name)),
+            (None, None) => {}
+        }
+        if let Some(framegroup) = &self.framegroup {
+            parts.push(format!("framegroup '{}'", framegroup));
+        }
+        if let Some(direction) = &self.direction {
+            parts.push(format!("direction '{}'", direction));
+        }
+        if let Some(path) = &self.path {
+            parts.push(format!("at `{}`", path));
+        }
+
+        match (parts.is_empty(), &self.location) {
+            (true, Some(location)) => write!(f, "{}", location),
+            (false, Some(location)) => write!(f, "{} ({})", parts.join(", "), location),
+            (_, None) => write!(f, "{}", parts.join(", ")),
+        }
+    }
+}
+
+/// Gravidade de um diagnóstico
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    /// Não impede a compilação, mas provavelmente é um engano
+    Warning,
+    /// Impede a compilação
+    Error,
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Severity::Warning => write!(f, "warning"),
+            Severity::Error => write!(f, "error"),
+        }
+    }
+}
+
+/// Problema encontrado ao validar um arquivo de appearances
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    /// Regra de lint que gerou o diagnóstico (None na validação)
+    pub rule:     Option<&'static str>,
+    pub message:  String,
+    pub context:  ErrorContext,
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.rule {
+            Some(rule) => write!(f, "{}[{}]: {} ({})", self.severity, rule, self.message, self.context),
+            None => write!(f, "{}: {} ({})", self.severity, self.message, self.context),
+        }
+    }
+}
+
+/// Posição de cada chave e elemento de um documento JSON, pelo caminho no
+/// formato de `ErrorContext::path` (`appearances[0].framegroups[1].name`)
+///
+/// Chaves apontam para o início do nome (depois da aspa) e elementos de array
+/// para o início do valor. Retorna None se o texto não for JSON.
+pub(crate) fn json_spans(text: &str) -> Option<HashMap<String, usize>> {
+    let mut parser = SpanParser {
+        text:     text.as_bytes(),
+        position: 0,
+        spans:    HashMap::new(),
+    };
+
+    parser.value("")?;
+    parser.skip_whitespace();
+    (parser.position == text.len()).then_some(parser.spans)
+}
+
+/// Parser JSON que só registra as posições (os valores são lidos pelo serde_json)
+struct SpanParser<'a> {
+    text:     &'a [u8],
+    position: usize,
+    spans:    HashMap<String, usize>,
+}
+
+impl SpanParser<'_> {
+    fn value(&mut self, path: &str) -> Option<()> {
+        self.skip_whitespace();
+        match *self.text.get(self.position)? {
+            b'{' => self.object(path),
+            b'[' => self.array(path),
+            b'"' => self.string().map(drop),
+            _ => self.scalar(),
+        }
+    }
+
+    fn object(&mut self, path: &str) -> Option<()> {
+        self.position += 1;
+        if self.next_is(b'}') {
+            return Some(());
+        }
+
+        loop {
+            self.skip_whitespace();
+            let name = self.position + 1;
+            let key = self.string()?;
+            let child = if path.is_empty() { key } else { format!("{}.{}", path, key) };
+
+            self.expect(b':')?;
+            self.value(&child)?;
+            self.spans.insert(child, name);
+
+            if !self.next_is(b',') {
+                return self.expect(b'}');
+            }
+        }
+    }
+
+    fn array(&mut self, path: &str) -> Option<()> {
+        self.position += 1;
+        if self.next_is(b']') {
+            return Some(());
+        }
+
+        let mut index = 0;
+        loop {
+            self.skip_whitespace();
+            let child = format!("{}[{}]", path, index);
+            self.spans.insert(child.clone(), self.position);
+            self.value(&child)?;
+            index += 1;
+
+            if !self.next_is(b',') {
+                return self.expect(b']');
+            }
+        }
+    }
+
+    /// Lê uma string (com escapes) a partir da aspa de abertura
+    fn string(&mut self) -> Option<String> {
+        let start = self.position;
+        if self.text.get(start) != Some(&b'"') {
+            return None;
+        }
+
+        let mut escaped = false;
+        for (offset, &byte) in self.text[start + 1..].iter().enumerate() {
+            match byte {
+                _ if escaped => escaped = false,
+                b'\\' => escaped = true,
+                b'"' => {
+                    self.position = start + offset + 2;
+                    return serde_json::from_slice(&self.text[start..self.position]).ok();
+                }
+                _ => {}
+            }
+        }
+
+        None
+    }
+
+    /// Número, `true`, `false` ou `null`
+    fn scalar(&mut self) -> Option<()> {
+        let start = self.position;
+        while self
+            .text
+            .get(self.position)
+            .is_some_and(|byte| !matches!(byte, b',' | b'}' | b']') && !byte.is_ascii_whitespace())
+        {
+            self.position += 1;
+        }
+        (self.position > start).then_some(())
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self.text.get(self.position).is_some_and(u8::is_ascii_whitespace) {
+            self.position += 1;
+        }
+    }
+
+    /// Consome `byte` (depois de espaços) se ele for o próximo
+    fn next_is(&mut self, byte: u8) -> bool {
+        self.skip_whitespace();
+        let found = self.text.get(self.position) == Some(&byte);
+        if found {
+            self.position += 1;
+        }
+        found
+    }
+
+    fn expect(&mut self, byte: u8) -> Option<()> {
+        self.next_is(byte).then_some(())
+    }
+}
+
+/// Converte um offset em bytes para (linha, coluna), começando em 1
+pub(crate) fn line_column(text: &str, offset: usize) -> (usize, usize) {
+    let before = &text[..offset.min(text.len())];
+    let line_start = before.rfind('\n').map_or(0, |newline| newline + 1);
+    (
+        before.matches('\n').count() + 1,
+        before[line_start..].chars().count() + 1,
+    )
+}
+
+/// Converte (linha, coluna) em offset em bytes
+fn offset_of(text: &str, line: usize, column: usize) -> usize {
+    let line_start: usize = text
+        .split_inclusive('\n')
+        .take(line.saturating_sub(1))
+        .map(str::len)
+        .sum();
+
+    text[line_start..]
+        .char_indices()
+        .nth(column.saturating_sub(1))
+        .map_or(text.len(), |(index, _)| line_start + index)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_json_spans_follow_the_error_path_format() {
+        let json = r#"{
+  "version": 2,
+  "appearances": [
+    { "id": 1, "name": "id: 3", "metadata": { "id": 2 } },
+    {
+      "id": 2,
+      "framegroups": [{ "name": "walk", "animations": { "north": { "frame_count": 8 } } }]
+    }
+  ]
+}"#;
+        let spans = json_spans(json).unwrap();
+        let at = |path: &str| line_column(json, spans[path]);
+
+        assert_eq!(at("version"), (2, 4));
+        assert_eq!(at("appearances[0]"), (4, 5));
+        assert_eq!(at("appearances[0].metadata.id"), (4, 48));
+        assert_eq!(at("appearances[1].id"), (6, 8));
+        assert_eq!(at("appearances[1].framegroups[0].animations.north.frame_count"), (7, 69));
+
+        // Outros formatos não têm posições
+        assert!(json_spans("[[appearances]]\nid = 1\n").is_none());
+        assert!(json_spans("appearances:\n  - id: 1\n").is_none());
+        assert!(json_spans("(version: 2)").is_none());
+    }
+
+    #[test]
+    fn test_locate_falls_back_to_the_innermost_existing_item() {
+        let root = crate::test_support::TempDir::new("diagnostic");
+        let file = root.write(
+            "appearances.json",
+            r#"{
+  "appearances": [
+    { "id": 7, "name": "rock" },
+    { "id": 9, "name": "orc", "framegroups": [{ "name": "idle" }, { "name": "walk" }] }
+  ]
+}"#,
+        );
+
+        let start = locate_in_file(&file, "appearances[1]").unwrap();
+        let locate = |path: &str, framegroup: Option<&str>| {
+            let location = locate_in_appearance(&start, path, framegroup).unwrap();
+            (location.line, location.column)
+        };
+
+        // O índice do arquivo mesclado é trocado pelo do arquivo de origem
+        assert_eq!(locate("appearances[5].name", None), (4, 17));
+        assert_eq!(locate("appearances[5].size", None), (4, 5));
+        assert_eq!(locate("appearances[5].framegroups[0].animations", Some("walk")), (4, 67));
+        assert_eq!(locate("appearances[5].framegroups[0].name", Some("jump")), (4, 5));
+
+        assert_eq!(locate_in_file(&file, "version"), None);
+        assert!(locate_in_file(&root.write("appearances.toml", "version = 2\n"), "version").is_none());
+    }
+}
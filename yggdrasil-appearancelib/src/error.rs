@@ -1,3 +1,6 @@
+use crate::diagnostic::{ErrorContext, SourceLocation};
+use crate::types::{Appearance, Direction};
+use std::path::PathBuf;
 use thiserror::Error;
 
 #[derive(Error, Debug)]
@@ -8,26 +11,133 @@ pub enum AppearanceError {
     #[error("JSON parsing error: {0}")]
     Json(#[from] serde_json::Error),
 
+    #[error("{format} parsing error: {message}")]
+    Parse { format: &'static str, message: String },
+
     #[error("Image error: {0}")]
     Image(#[from] image::ImageError),
 
     #[error(
-        "Invalid sprite dimensions for appearance '{name}' animation '{animation}': expected {expected_width}x{expected_height}, got {actual_width}x{actual_height}"
+        "Invalid spritesheet dimensions for '{path}': expected {expected_width}x{expected_height}, got {actual_width}x{actual_height}"
     )]
     InvalidDimensions {
-        name:            String,
-        animation:       String,
+        path:            String,
         expected_width:  u32,
         expected_height: u32,
         actual_width:    u32,
         actual_height:   u32,
     },
 
+    #[error("Duplicate appearance name '{name}' (IDs {first_id} and {second_id})")]
+    DuplicateName {
+        name:      String,
+        first_id:  u32,
+        second_id: u32,
+    },
+
+    #[error("Duplicate appearance ID {id} in '{first}' and '{second}'")]
+    DuplicateId {
+        id:     u64,
+        first:  String,
+        second: String,
+    },
+
     #[error("Sprite file not found: {path}")]
     SpriteNotFound { path: String },
 
+    #[error("Alpha masks file not found: {path} (compile with alpha masks enabled)")]
+    MasksNotFound { path: String },
+
+    #[error("Spritesheet '{path}' not found (tried {})", display_paths(.tried))]
+    SpritesheetNotFound { path: String, tried: Vec<PathBuf> },
+
+    #[error("'{path}' is not a compiled appearances file")]
+    InvalidMagic { path: String },
+
+    #[error(
+        "'{path}' uses format version {found}, but this loader reads version {expected}; recompile the appearances"
+    )]
+    UnsupportedFormat {
+        path:     String,
+        found:    u32,
+        expected: u32,
+    },
+
     #[error("Invalid appearance data: {0}")]
     InvalidData(String),
+
+    /// Outro erro com a posição onde aconteceu
+    ///
+    /// Os erros de `parse_appearances`, `read_value` e da compilação chegam
+    /// embrulhados nesta variante. Para testar o tipo do erro, use `root()`:
+    /// `matches!(error.root(), AppearanceError::SpriteNotFound { .. })`.
+    #[error("{context}: {source}")]
+    Context {
+        context: Box<ErrorContext>,
+        source:  Box<AppearanceError>,
+    },
+}
+
+impl AppearanceError {
+    /// Retorna onde o erro aconteceu (appearance, framegroup, direção, arquivo)
+    pub fn context(&self) -> Option<&ErrorContext> {
+        match self {
+            AppearanceError::Context { context, .. } => Some(context),
+            _ => None,
+        }
+    }
+
+    /// Retorna o erro sem o contexto
+    pub fn root(&self) -> &AppearanceError {
+        match self {
+            AppearanceError::Context { source, .. } => source.root(),
+            error => error,
+        }
+    }
+
+    /// Adiciona ou completa o contexto do erro
+    pub(crate) fn with_context(self, update: impl FnOnce(&mut ErrorContext)) -> Self {
+        let (mut context, source) = match self {
+            AppearanceError::Context { context, source } => (context, source),
+            error => (Box::default(), Box::new(error)),
+        };
+        update(&mut context);
+        AppearanceError::Context { context, source }
+    }
+
+    /// Marca o campo com problema (ex.: `spritesheet`)
+    pub(crate) fn in_field(self, field: &str) -> Self {
+        self.with_context(|context| context.in_field(field))
+    }
+
+    /// Marca a direção da animação em que o erro aconteceu
+    pub(crate) fn in_direction(self, direction: Option<Direction>) -> Self {
+        self.with_context(|context| context.in_direction(direction))
+    }
+
+    /// Marca o framegroup em que o erro aconteceu
+    pub(crate) fn in_framegroup(self, index: usize, name: &str) -> Self {
+        self.with_context(|context| context.in_framegroup(index, name))
+    }
+
+    /// Marca a appearance em que o erro aconteceu e localiza o erro no arquivo de origem
+    pub(crate) fn in_appearance(self, index: usize, appearance: &Appearance) -> Self {
+        self.with_context(|context| context.in_appearance(index, appearance))
+    }
+
+    /// Marca a posição do erro no arquivo
+    pub(crate) fn at(self, location: SourceLocation) -> Self {
+        self.with_context(|context| context.location = Some(location))
+    }
+}
+
+/// Lista de caminhos para mensagens de erro (`'a', 'b'`)
+fn display_paths(paths: &[PathBuf]) -> String {
+    paths
+        .iter()
+        .map(|path| format!("'{}'", path.display()))
+        .collect::<Vec<_>>()
+        .join(", ")
 }
 
 pub type Result<T> = std::result::Result<T, AppearanceError>;
@@ -0,0 +1,219 @@
+use crate::diagnostic::{SourceLocation, line_column};
+use crate::error::{AppearanceError, Result};
+use serde_json::Value;
+use std::fs;
+use std::path::Path;
+
+/// Formato de autoria do appearances (escolhido pela extensão do arquivo)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    Json,
+    Toml,
+    Yaml,
+    Ron,
+}
+
+impl Format {
+    /// Extensões reconhecidas
+    pub const EXTENSIONS: [&'static str; 5] = ["json", "toml", "yaml", "yml", "ron"];
+
+    /// Determina o formato pela extensão (`.json`, `.toml`, `.yaml`/`.yml`, `.ron`)
+    pub fn from_path(path: &Path) -> Result<Self> {
+        let extension = path
+            .extension()
+            .and_then(|extension| extension.to_str())
+            .unwrap_or_default();
+
+        match extension.to_ascii_lowercase().as_str() {
+            "json" => Ok(Format::Json),
+            "toml" => Ok(Format::Toml),
+            "yaml" | "yml" => Ok(Format::Yaml),
+            "ron" => Ok(Format::Ron),
+            _ => Err(AppearanceError::InvalidData(format!(
+                "Unsupported appearances format '{}', expected one of: {}",
+                path.display(),
+                Self::EXTENSIONS.join(", ")
+            ))),
+        }
+    }
+
+    /// Retorna true se o arquivo tem uma extensão reconhecida
+    pub fn is_supported(path: &Path) -> bool {
+        Self::from_path(path).is_ok()
+    }
+
+    /// Nome do formato para mensagens
+    pub fn name(&self) -> &'static str {
+        match self {
+            Format::Json => "JSON",
+            Format::Toml => "TOML",
+            Format::Yaml => "YAML",
+            Format::Ron => "RON",
+        }
+    }
+
+    /// Lê o conteúdo como `serde_json::Value`
+    pub fn parse(&self, contents: &str) -> Result<Value> {
+        self.parse_with_position(contents).map_err(|(error, _)| error)
+    }
+
+    /// Lê o conteúdo, retornando no erro a posição (linha, coluna) quando o formato a informa
+    fn parse_with_position(
+        &self, contents: &str,
+    ) -> std::result::Result<Value, (AppearanceError, Option<(usize, usize)>)> {
+        match self {
+            Format::Json => serde_json::from_str(contents).map_err(|e| {
+                let position = (e.line() > 0).then(|| (e.line(), e.column()));
+                (AppearanceError::Json(e), position)
+            }),
+            Format::Toml => toml::from_str(contents).map_err(|e| {
+                let position = e.span().map(|span| line_column(contents, span.start));
+                (self.error(e.message()), position)
+            }),
+            Format::Yaml => serde_yaml::from_str(contents).map_err(|e| {
+                let position = e.location().map(|location| (location.line(), location.column()));
+                (self.error(e), position)
+            }),
+            Format::Ron => ron::from_str(contents).map_err(|e| {
+                let position = (e.span.start.line > 0).then_some((e.span.start.line, e.span.start.col));
+                (self.error(e.code), position)
+            }),
+        }
+    }
+
+    /// Escreve o valor neste formato
+    pub fn serialize(&self, value: &Value) -> Result<String> {
+        match self {
+            Format::Json => Ok(serde_json::to_string_pretty(value)?),
+            Format::Toml => toml::to_string_pretty(value).map_err(|e| self.error(e)),
+            Format::Yaml => serde_yaml::to_string(value).map_err(|e| self.error(e)),
+            Format::Ron => {
+                let config = ron::ser::PrettyConfig::default().struct_names(false);
+                ron::ser::to_string_pretty(value, config).map_err(|e| self.error(e))
+            }
+        }
+    }
+
+    fn error(&self, error: impl std::fmt::Display) -> AppearanceError {
+        AppearanceError::Parse {
+            format:  self.name(),
+            message: error.to_string(),
+        }
+    }
+}
+
+/// Lê um arquivo em qualquer formato suportado como `serde_json::Value`
+///
+/// Erros de sintaxe levam o arquivo, a linha e a coluna no contexto.
+pub fn read_value(path: &Path) -> Result<Value> {
+    let contents = fs::read_to_string(path)?;
+
+    Format::from_path(path)?
+        .parse_with_position(&contents)
+        .map_err(|(error, position)| match position {
+            Some((line, column)) => error.at(SourceLocation {
+                file: path.to_path_buf(),
+                line,
+                column,
+            }),
+            None => error,
+        })
+}
+
+/// Converte um arquivo de appearances entre formatos (pelas extensões)
+///
+/// A conversão é feita sem resolver `include` e `extends`, preservando a
+/// estrutura do arquivo original.
+pub fn convert_file(input: &Path, output: &Path) -> Result<()> {
+    let value = read_value(input)?;
+    let contents = Format::from_path(output)?.serialize(&value)?;
+    fs::write(output, contents)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::AppearancesFile;
+    use serde_json::json;
+
+    #[test]
+    fn test_formats_deserialize_to_same_file() {
+        let toml = r#"
+            version = 2
+
+            [[appearances]]
+            id = 1
+            name = "torch"
+            size = 32
+
+            [[appearances.framegroups]]
+            name = "lit"
+            spritesheet = "torch.png"
+
+            [appearances.framegroups.animations.null]
+            frame_count = 4
+            duration = 100
+        "#;
+
+        let yaml = r#"
+            version: 2
+            appearances:
+              - id: 1
+                name: torch
+                size: 32
+                framegroups:
+                  - name: lit
+                    spritesheet: torch.png
+                    animations:
+                      "null": { frame_count: 4, duration: 100 }
+        "#;
+
+        let ron = r#"(
+            version: 2,
+            appearances: [(
+                id: 1,
+                name: "torch",
+                size: 32,
+                framegroups: [(
+                    name: "lit",
+                    spritesheet: "torch.png",
+                    animations: { "null": (frame_count: 4, duration: 100) },
+                )],
+            )],
+        )"#;
+
+        for (format, contents) in [(Format::Toml, toml), (Format::Yaml, yaml), (Format::Ron, ron)] {
+            let value = format.parse(contents).unwrap();
+            let file: AppearancesFile = serde_json::from_value(value).unwrap();
+            let animation = &file.appearances[0].framegroups[0].animations[&None];
+            assert_eq!(animation.frame_count, 4, "{}", format.name());
+            assert_eq!(animation.duration, Some(100), "{}", format.name());
+        }
+    }
+
+    #[test]
+    fn test_round_trip_between_formats() {
+        let value = json!({
+            "version": 2,
+            "appearances": [{
+                "id": 1,
+                "name": "orc",
+                "size": 64,
+                "framegroups": [{
+                    "name": "walk",
+                    "spritesheet": "walk.png",
+                    "animations": { "north": { "frame_count": 8, "duration": 100 } }
+                }]
+            }]
+        });
+
+        for format in [Format::Json, Format::Toml, Format::Yaml, Format::Ron] {
+            let text = format.serialize(&value).unwrap();
+            assert_eq!(format.parse(&text).unwrap(), value, "{}", format.name());
+        }
+
+        assert_eq!(Format::from_path(Path::new("a/appearances.YML")).unwrap(), Format::Yaml);
+        assert!(Format::from_path(Path::new("appearances.xml")).is_err());
+    }
+}
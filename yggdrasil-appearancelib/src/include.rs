@@ -0,0 +1,293 @@
+use crate::error::{AppearanceError, Result};
+use crate::format::{Format, read_value};
+use serde_json::{Map, Value};
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Estado da leitura de um appearances.json com fragmentos
+#[derive(Default)]
+struct IncludeState {
+    /// Arquivos já lidos (caminho canônico)
+    visited:            HashSet<PathBuf>,
+    /// Arquivo de origem de cada ID de appearance
+    ids:                HashMap<u64, PathBuf>,
+    /// Arquivo de origem de cada template
+    templates:          HashMap<String, PathBuf>,
+    /// IDs repetidos não interrompem a leitura (ficam para a validação)
+    keep_duplicate_ids: bool,
+    /// Marca cada framegroup com o arquivo que o define (`SOURCE_KEY`)
+    mark_sources:       bool,
+}
+
+/// Chave temporária com o arquivo de origem de um framegroup
+///
+/// Inserida por `load_marked` e removida por `parse_appearances` antes da
+/// deserialização. Como templates substituem arrays inteiros, cada framegroup
+/// vem sempre de um único arquivo, e a marca acompanha o seu `spritesheet`.
+pub(crate) const SOURCE_KEY: &str = "$source";
+
+/// Lê o arquivo e mescla recursivamente os fragmentos listados em `include`
+///
+/// Cada entrada de `include` é um arquivo ou uma pasta (todos os arquivos de
+/// formatos suportados dela, em ordem alfabética), relativa ao arquivo que a declara. As `appearances` e
+/// os `templates` dos fragmentos são adicionados ao arquivo principal; IDs ou
+/// templates repetidos geram erro com os dois arquivos envolvidos.
+pub fn load_with_includes(path: &Path) -> Result<Value> {
+    load_with_sources(path).map(|(file, _)| file)
+}
+
+/// Como `load_with_includes`, retornando também o arquivo de origem de cada ID
+pub fn load_with_sources(path: &Path) -> Result<(Value, HashMap<u64, PathBuf>)> {
+    load(path, IncludeState::default())
+}
+
+/// Como `load_with_sources`, marcando cada framegroup com o seu arquivo (`SOURCE_KEY`)
+///
+/// Com `keep_duplicate_ids`, appearances com IDs repetidos são mantidas em
+/// vez de gerar erro (usado pela validação, que as reporta junto com os
+/// demais problemas). A origem registrada de um ID repetido é a do primeiro
+/// arquivo.
+pub(crate) fn load_marked(path: &Path, keep_duplicate_ids: bool) -> Result<(Value, HashMap<u64, PathBuf>)> {
+    load(
+        path,
+        IncludeState {
+            keep_duplicate_ids,
+            mark_sources: true,
+            ..Default::default()
+        },
+    )
+}
+
+fn load(path: &Path, mut state: IncludeState) -> Result<(Value, HashMap<u64, PathBuf>)> {
+    let file = load_file(path, &mut state)?;
+    Ok((file, state.ids))
+}
+
+/// Lê um arquivo, registra suas appearances e mescla seus includes
+fn load_file(path: &Path, state: &mut IncludeState) -> Result<Value> {
+    state.visited.insert(fs::canonicalize(path)?);
+
+    let mut file = read_value(path)?;
+    if !file.is_object() {
+        return Err(AppearanceError::InvalidData(format!(
+            "'{}' must contain an object with `appearances`",
+            path.display()
+        )));
+    }
+
+    register(&file, path, state)?;
+    if state.mark_sources {
+        mark_sources(&mut file, path);
+    }
+
+    let includes = match file.as_object_mut().and_then(|fields| fields.remove("include")) {
+        Some(Value::Array(entries)) => entries,
+        Some(_) => {
+            return Err(AppearanceError::InvalidData(format!(
+                "'{}': `include` must be a list of paths",
+                path.display()
+            )));
+        }
+        None => return Ok(file),
+    };
+
+    let base_dir = path.parent().unwrap_or(Path::new(""));
+
+    for entry in includes {
+        let entry = entry.as_str().ok_or_else(|| {
+            AppearanceError::InvalidData(format!("'{}': `include` entries must be strings", path.display()))
+        })?;
+        let entry_path = base_dir.join(entry);
+
+        if !entry_path.exists() {
+            return Err(AppearanceError::InvalidData(format!(
+                "'{}' includes '{}', which does not exist",
+                path.display(),
+                entry_path.display()
+            )));
+        }
+
+        for fragment_path in fragment_files(&entry_path)? {
+            // Um arquivo incluído mais de uma vez (ou o próprio arquivo principal) é lido só uma vez
+            if state.visited.contains(&fs::canonicalize(&fragment_path)?) {
+                continue;
+            }
+
+            let fragment = load_file(&fragment_path, state)?;
+            append(&mut file, fragment);
+        }
+    }
+
+    Ok(file)
+}
+
+/// Lista os arquivos de uma entrada de `include` (pastas são lidas recursivamente)
+fn fragment_files(path: &Path) -> Result<Vec<PathBuf>> {
+    if !path.is_dir() {
+        return Ok(vec![path.to_path_buf()]);
+    }
+
+    let mut entries: Vec<PathBuf> = fs::read_dir(path)?
+        .map(|entry| entry.map(|entry| entry.path()))
+        .collect::<std::io::Result<_>>()?;
+    entries.sort();
+
+    let mut files = Vec::new();
+    for entry in entries {
+        if entry.is_dir() {
+            files.extend(fragment_files(&entry)?);
+        } else if Format::is_supported(&entry) {
+            files.push(entry);
+        }
+    }
+
+    Ok(files)
+}
+
+/// Registra a origem dos IDs e templates de um arquivo, detectando repetições
+fn register(file: &Value, path: &Path, state: &mut IncludeState) -> Result<()> {
+    let ids = file["appearances"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(|appearance| appearance.get("id")?.as_u64());
+
+    for id in ids {
+        match state.ids.get(&id) {
+            Some(_) if state.keep_duplicate_ids => {}
+            Some(first) => {
+                return Err(AppearanceError::DuplicateId {
+                    id,
+                    first: first.display().to_string(),
+                    second: path.display().to_string(),
+                });
+            }
+            None => {
+                state.ids.insert(id, path.to_path_buf());
+            }
+        }
+    }
+
+    for name in file["templates"].as_object().into_iter().flat_map(Map::keys) {
+        if let Some(first) = state.templates.insert(name.clone(), path.to_path_buf()) {
+            return Err(AppearanceError::InvalidData(format!(
+                "Template '{}' is defined in both '{}' and '{}'",
+                name,
+                first.display(),
+                path.display()
+            )));
+        }
+    }
+
+    Ok(())
+}
+
+/// Marca os framegroups das appearances e dos templates com o arquivo de origem
+fn mark_sources(file: &mut Value, path: &Path) {
+    let source = Value::String(path.to_string_lossy().into_owned());
+
+    let mark = |item: &mut Value| {
+        let framegroups = item.get_mut("framegroups").and_then(Value::as_array_mut);
+        for framegroup in framegroups.into_iter().flatten() {
+            if let Some(fields) = framegroup.as_object_mut() {
+                fields.insert(SOURCE_KEY.to_string(), source.clone());
+            }
+        }
+    };
+
+    if let Some(appearances) = file.get_mut("appearances").and_then(Value::as_array_mut) {
+        appearances.iter_mut().for_each(mark);
+    }
+    if let Some(templates) = file.get_mut("templates").and_then(Value::as_object_mut) {
+        templates.values_mut().for_each(mark);
+    }
+}
+
+/// Adiciona as appearances e templates de um fragmento ao arquivo principal
+fn append(file: &mut Value, fragment: Value) {
+    let Value::Object(mut fragment) = fragment else {
+        return;
+    };
+    let fields = file.as_object_mut().expect("arquivo principal é um objeto");
+
+    if let Some(Value::Array(appearances)) = fragment.remove("appearances") {
+        match fields.entry("appearances").or_insert_with(|| Value::Array(Vec::new())) {
+            Value::Array(existing) => existing.extend(appearances),
+            other => *other = Value::Array(appearances),
+        }
+    }
+
+    if let Some(Value::Object(templates)) = fragment.remove("templates") {
+        match fields.entry("templates").or_insert_with(|| Value::Object(Map::new())) {
+            Value::Object(existing) => existing.extend(templates),
+            other => *other = Value::Object(templates),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::TempDir;
+
+    /// Cria uma pasta temporária com os arquivos informados
+    fn write_files(name: &str, files: &[(&str, &str)]) -> TempDir {
+        let root = TempDir::new(name);
+        for (path, contents) in files {
+            root.write(path, contents);
+        }
+        root
+    }
+
+    #[test]
+    fn test_include_files_and_directories() {
+        let root = write_files(
+            "merge",
+            &[
+                (
+                    "appearances.json",
+                    r#"{ "version": 2, "include": ["creatures", "items.json"], "appearances": [{ "id": 1 }] }"#,
+                ),
+                ("creatures/orc.json", r#"{ "appearances": [{ "id": 20 }] }"#),
+                ("creatures/undead/skeleton.json", r#"{ "appearances": [{ "id": 30 }] }"#),
+                ("creatures/notes.txt", "ignorado"),
+                (
+                    "items.json",
+                    r#"{ "templates": { "item": { "size": 32 } }, "include": ["appearances.json"], "appearances": [{ "id": 40 }] }"#,
+                ),
+            ],
+        );
+
+        let file = load_with_includes(&root.join("appearances.json")).unwrap();
+        let ids: Vec<u64> = file["appearances"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|a| a["id"].as_u64().unwrap())
+            .collect();
+        assert_eq!(ids, vec![1, 20, 30, 40]);
+        assert_eq!(file["templates"]["item"]["size"], 32);
+        assert!(file.get("include").is_none());
+    }
+
+    #[test]
+    fn test_duplicate_ids_name_both_files() {
+        let root = write_files(
+            "duplicate",
+            &[
+                (
+                    "appearances.json",
+                    r#"{ "include": ["a.json", "b.json"], "appearances": [] }"#,
+                ),
+                ("a.json", r#"{ "appearances": [{ "id": 7 }] }"#),
+                ("b.json", r#"{ "appearances": [{ "id": 7 }] }"#),
+            ],
+        );
+
+        let error = load_with_includes(&root.join("appearances.json"))
+            .unwrap_err()
+            .to_string();
+        assert!(error.contains("a.json") && error.contains("b.json"), "{}", error);
+    }
+}
@@ -1,21 +1,52 @@
+pub mod animation;
 pub mod compiler;
+pub mod diagnostic;
 pub mod error;
+pub mod format;
+pub mod include;
+pub mod lint;
 pub mod loaded_types;
 pub mod loader;
+pub mod mask;
 pub mod parser;
+pub mod resolver;
+pub mod schema;
 pub mod sprite;
+pub mod template;
 pub mod types;
+pub mod validate;
+
+mod check;
+
+#[cfg(test)]
+mod test_support;
 
 // Compiler exports
-pub use compiler::{CompilationResult, compile_appearances};
+pub use compiler::{
+    CompilationResult, CompileOptions, MinimapColorMode, compile_appearances, compile_appearances_with_options,
+};
 
 // Loader exports
-pub use loaded_types::{AppearanceDatabase, LoadedAnimation, LoadedAppearance, LoadedFrameGroup, LoadedSprite};
+pub use loaded_types::{
+    AppearanceDatabase, FrameTiming, LoadedAnimation, LoadedAppearance, LoadedFrameGroup, LoadedSprite,
+};
 pub use loader::{AppearanceLoader, load_all, load_database_only};
+pub use mask::SpriteMask;
+
+// Runtime exports
+pub use animation::{AnimationPlayer, AnimationStateMachine};
 
 // Common exports
+pub use diagnostic::{Diagnostic, ErrorContext, Severity, SourceLocation};
 pub use error::{AppearanceError, Result};
-pub use parser::parse_appearances_json;
+pub use format::{Format, convert_file};
+pub use lint::{LintConfig, LintRule, RuleKind, lint_appearances};
+pub use parser::{parse_appearances, parse_appearances_for_validation, parse_appearances_json};
+pub use resolver::PathResolver;
+pub use schema::{appearances_schema, write_schema};
 pub use types::{
-    Animation, Appearance, AppearancesFile, Direction, FrameGroup, Orientation, SpriteData, SpriteMetadata,
+    Animation, Appearance, AppearancesFile, AttachmentPoint, Category, CollisionShape, Color, Direction, DisabledLight,
+    Flicker, Footprint, FrameDuration, FrameEvent, FrameGroup, FrameGroupLight, Light, Metadata, Orientation,
+    PlaybackMode, Properties, Rect, RotationMode, ShapeKind, SpriteData, SpriteMetadata, StartFrame,
 };
+pub use validate::{validate_appearances, validate_appearances_with_resolver};
@@ -0,0 +1,442 @@
+use crate::diagnostic::{Diagnostic, ErrorContext, Severity};
+use crate::error::Result;
+use crate::format::read_value;
+use crate::types::{Appearance, AppearancesFile, Category, Direction, FrameGroup, direction_key};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::path::Path;
+
+/// Configuração do lint: as regras da casa aplicadas a um arquivo de appearances
+///
+/// Não há regras embutidas; as do projeto ficam no `lint.toml` do manager.
+/// Pode ser lida de um arquivo em qualquer formato suportado (ex.: `lint.toml`):
+///
+/// ```toml
+/// [[rules]]
+/// rule = "required_framegroups"
+/// category = "creature"
+/// framegroups = ["idle", "walk", "death"]
+/// directions = ["north", "east", "south", "west"]
+///
+/// [[rules]]
+/// rule = "allowed_sizes"
+/// severity = "warning"
+/// sizes = [32, 64]
+/// ```
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct LintConfig {
+    #[serde(default)]
+    pub rules: Vec<LintRule>,
+}
+
+impl LintConfig {
+    /// Lê a configuração de um arquivo (.json, .toml, .yaml, .yml ou .ron)
+    pub fn from_file(path: &Path) -> Result<Self> {
+        Ok(serde_json::from_value(read_value(path)?)?)
+    }
+}
+
+/// Uma regra de lint, com gravidade e as appearances às quais se aplica
+///
+/// Campos desconhecidos são rejeitados: um `catgory` digitado errado não pode
+/// fazer a regra valer para todas as appearances.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(try_from = "RawLintRule")]
+pub struct LintRule {
+    #[serde(flatten)]
+    pub kind:     RuleKind,
+    /// Gravidade dos diagnósticos (padrão: error)
+    #[serde(default = "default_severity")]
+    pub severity: Severity,
+    /// Aplica a regra só às appearances desta categoria
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub category: Option<Category>,
+    /// Aplica a regra só às appearances com alguma destas tags
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags:     Vec<String>,
+}
+
+fn default_severity() -> Severity {
+    Severity::Error
+}
+
+/// Forma lida de uma `LintRule`: os campos comuns e o resto da tabela, que
+/// precisa descrever exatamente uma `RuleKind`
+#[derive(Deserialize)]
+struct RawLintRule {
+    #[serde(default = "default_severity")]
+    severity: Severity,
+    #[serde(default)]
+    category: Option<Category>,
+    #[serde(default)]
+    tags:     Vec<String>,
+    #[serde(flatten)]
+    kind:     Map<String, Value>,
+}
+
+impl TryFrom<RawLintRule> for LintRule {
+    type Error = String;
+
+    fn try_from(raw: RawLintRule) -> std::result::Result<Self, Self::Error> {
+        // Variantes sem campos não passam pelo `deny_unknown_fields`
+        let unexpected = raw.kind.keys().find(|key| *key != "rule").cloned();
+        let kind: RuleKind = serde_json::from_value(Value::Object(raw.kind)).map_err(|error| error.to_string())?;
+
+        if let (RuleKind::NonDirectional, Some(key)) = (&kind, unexpected) {
+            return Err(format!("unknown field `{}`, rule `{}` has no fields", key, kind.name()));
+        }
+
+        let empty = match &kind {
+            RuleKind::RequiredFramegroups { framegroups, .. } => framegroups.is_empty().then_some("framegroups"),
+            RuleKind::AllowedSizes { sizes } => sizes.is_empty().then_some("sizes"),
+            RuleKind::NonDirectional => None,
+        };
+        if let Some(field) = empty {
+            return Err(format!("`{}` of rule `{}` must not be empty", field, kind.name()));
+        }
+
+        Ok(LintRule {
+            kind,
+            severity: raw.severity,
+            category: raw.category,
+            tags: raw.tags,
+        })
+    }
+}
+
+impl LintRule {
+    /// Regra com gravidade `error` aplicada a todas as appearances
+    pub fn new(kind: RuleKind) -> Self {
+        LintRule {
+            kind,
+            severity: default_severity(),
+            category: None,
+            tags: Vec::new(),
+        }
+    }
+
+    pub fn for_category(mut self, category: Category) -> Self {
+        self.category = Some(category);
+        self
+    }
+
+    pub fn for_tags<I, S>(mut self, tags: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.tags = tags.into_iter().map(Into::into).collect();
+        self
+    }
+
+    pub fn with_severity(mut self, severity: Severity) -> Self {
+        self.severity = severity;
+        self
+    }
+
+    /// Retorna true se a regra vale para a appearance (categoria e tags)
+    pub fn applies_to(&self, appearance: &Appearance) -> bool {
+        let category = self
+            .category
+            .is_none_or(|category| appearance.category == Some(category));
+        let tags = self.tags.is_empty() || self.tags.iter().any(|tag| appearance.tags.contains(tag));
+        category && tags
+    }
+}
+
+/// O que uma regra de lint verifica
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "rule", rename_all = "snake_case", deny_unknown_fields)]
+pub enum RuleKind {
+    /// Framegroups obrigatórios, cada um com as direções listadas
+    RequiredFramegroups {
+        framegroups: Vec<String>,
+        /// Direções exigidas em cada framegroup (vazio = qualquer uma)
+        #[serde(default)]
+        directions:  Vec<Direction>,
+    },
+    /// Nenhum framegroup pode ter animações por direção ou `rotation`
+    NonDirectional,
+    /// Largura e altura da célula precisam estar na lista
+    AllowedSizes { sizes: Vec<u32> },
+}
+
+impl RuleKind {
+    /// Nome da regra, como aparece na configuração e nos diagnósticos
+    pub fn name(&self) -> &'static str {
+        match self {
+            RuleKind::RequiredFramegroups { .. } => "required_framegroups",
+            RuleKind::NonDirectional => "non_directional",
+            RuleKind::AllowedSizes { .. } => "allowed_sizes",
+        }
+    }
+}
+
+/// Aplica as regras de lint a todas as appearances
+///
+/// Diferente de `validate_appearances`, não procura erros que impedem a
+/// compilação: verifica as convenções do projeto descritas em `config`.
+/// Os diagnósticos saem na ordem das appearances e, dentro de cada uma, na
+/// ordem das regras.
+pub fn lint_appearances(appearances_file: &AppearancesFile, config: &LintConfig) -> Vec<Diagnostic> {
+    let mut diagnostics = Vec::new();
+
+    for (index, appearance) in appearances_file.appearances.iter().enumerate() {
+        for rule in config.rules.iter().filter(|rule| rule.applies_to(appearance)) {
+            let mut report = |framegroup: Option<(usize, &FrameGroup)>, field: &str, message: String| {
+                let mut context = ErrorContext::default();
+                context.in_field(field);
+                if let Some((framegroup_index, framegroup)) = framegroup {
+                    context.in_framegroup(framegroup_index, &framegroup.name);
+                }
+                context.in_appearance(index, appearance);
+
+                diagnostics.push(Diagnostic {
+                    severity: rule.severity,
+                    rule: Some(rule.kind.name()),
+                    message,
+                    context,
+                });
+            };
+
+            match &rule.kind {
+                RuleKind::RequiredFramegroups {
+                    framegroups,
+                    directions,
+                } => {
+                    for name in framegroups {
+                        let Some((framegroup_index, framegroup)) = appearance
+                            .framegroups
+                            .iter()
+                            .enumerate()
+                            .find(|(_, framegroup)| &framegroup.name == name)
+                        else {
+                            report(None, "framegroups", format!("Missing required framegroup '{}'", name));
+                            continue;
+                        };
+
+                        // Com `rotation` todas as direções são geradas a partir de uma só
+                        if framegroup.rotation.is_some() {
+                            continue;
+                        }
+
+                        let missing: Vec<String> = directions
+                            .iter()
+                            .filter(|direction| !framegroup.animations.contains_key(&Some(**direction)))
+                            .map(|direction| direction_key(Some(*direction)))
+                            .collect();
+                        if !missing.is_empty() {
+                            report(
+                                Some((framegroup_index, framegroup)),
+                                "animations",
+                                format!("Missing required direction(s): {}", missing.join(", ")),
+                            );
+                        }
+                    }
+                }
+                RuleKind::NonDirectional => {
+                    for (framegroup_index, framegroup) in appearance.framegroups.iter().enumerate() {
+                        let mut directional: Vec<String> = framegroup
+                            .animations
+                            .keys()
+                            .filter(|direction| direction.is_some())
+                            .map(|direction| direction_key(*direction))
+                            .collect();
+                        directional.sort();
+
+                        if framegroup.rotation.is_some() {
+                            report(
+                                Some((framegroup_index, framegroup)),
+                                "rotation",
+                                "Must not be directional, but uses `rotation`".to_string(),
+                            );
+                        } else if !directional.is_empty() {
+                            report(
+                                Some((framegroup_index, framegroup)),
+                                "animations",
+                                format!(
+                                    "Must not be directional, but has animations for: {}",
+                                    directional.join(", ")
+                                ),
+                            );
+                        }
+                    }
+                }
+                RuleKind::AllowedSizes { sizes } => {
+                    let Some((width, height)) = appearance.cell_size() else {
+                        continue;
+                    };
+                    if !sizes.contains(&width) || !sizes.contains(&height) {
+                        let allowed: Vec<String> = sizes.iter().map(u32::to_string).collect();
+                        // Aponta para o campo de onde veio a dimensão fora da lista
+                        let field = match (sizes.contains(&width), appearance.width, appearance.height) {
+                            (false, Some(_), _) => "width",
+                            (true, _, Some(_)) => "height",
+                            _ => "size",
+                        };
+                        report(
+                            None,
+                            field,
+                            format!(
+                                "Size {}x{} is not allowed (expected {})",
+                                width,
+                                height,
+                                allowed.join(" or ")
+                            ),
+                        );
+                    }
+                }
+            }
+        }
+    }
+
+    diagnostics
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::format::Format;
+
+    /// As regras da casa (o `lint.toml` do manager)
+    const HOUSE_RULES: &str = r#"
+        [[rules]]
+        rule = "required_framegroups"
+        category = "creature"
+        framegroups = ["idle", "walk", "death"]
+        directions = ["north", "east", "south", "west"]
+
+        [[rules]]
+        rule = "non_directional"
+        category = "effect"
+
+        [[rules]]
+        rule = "allowed_sizes"
+        sizes = [32, 64]
+    "#;
+
+    #[test]
+    fn test_house_rules_and_config_file() {
+        let file: AppearancesFile = serde_json::from_value(serde_json::json!({
+            "version": 2,
+            "appearances": [
+                {
+                    "id": 1,
+                    "name": "orc",
+                    "size": 32,
+                    "category": "creature",
+                    "framegroups": [
+                        {
+                            "name": "idle",
+                            "spritesheet": "idle.png",
+                            "rotation": "orthogonal",
+                            "animations": { "null": { "frame_count": 1 } }
+                        },
+                        {
+                            "name": "walk",
+                            "spritesheet": "walk.png",
+                            "animations": {
+                                "north": { "frame_count": 2 },
+                                "south": { "frame_count": 2 }
+                            }
+                        }
+                    ]
+                },
+                {
+                    "id": 2,
+                    "name": "fire",
+                    "size": 48,
+                    "category": "effect",
+                    "tags": ["legacy"],
+                    "framegroups": [
+                        {
+                            "name": "burn",
+                            "spritesheet": "fire.png",
+                            "animations": { "east": { "frame_count": 4 } }
+                        }
+                    ]
+                }
+            ]
+        }))
+        .unwrap();
+
+        let house_rules: LintConfig = serde_json::from_value(Format::Toml.parse(HOUSE_RULES).unwrap()).unwrap();
+        let diagnostics = lint_appearances(&file, &house_rules);
+        let messages: Vec<String> = diagnostics.iter().map(|d| d.to_string()).collect();
+        assert_eq!(diagnostics.len(), 4, "{:#?}", messages);
+
+        assert_eq!(diagnostics[0].rule, Some("required_framegroups"));
+        assert_eq!(diagnostics[0].message, "Missing required direction(s): east, west");
+        assert_eq!(diagnostics[0].context.framegroup.as_deref(), Some("walk"));
+        assert_eq!(diagnostics[1].message, "Missing required framegroup 'death'");
+        assert_eq!(diagnostics[2].rule, Some("non_directional"));
+        assert_eq!(
+            diagnostics[2].context.path.as_deref(),
+            Some("appearances[1].framegroups[0].animations")
+        );
+        assert_eq!(diagnostics[3].message, "Size 48x48 is not allowed (expected 32 or 64)");
+
+        // Regras por tag, com gravidade configurável
+        let config: LintConfig = serde_json::from_value(
+            Format::Toml
+                .parse(
+                    "[[rules]]\nrule = \"allowed_sizes\"\nseverity = \"warning\"\ntags = [\"legacy\"]\nsizes = [32]\n",
+                )
+                .unwrap(),
+        )
+        .unwrap();
+        let diagnostics = lint_appearances(&file, &config);
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].severity, Severity::Warning);
+        assert_eq!(diagnostics[0].context.appearance_name.as_deref(), Some("fire"));
+
+        // Campos desconhecidos e listas vazias são rejeitados
+        let parse = |toml: &str| {
+            serde_json::from_value::<LintConfig>(Format::Toml.parse(toml).unwrap())
+                .unwrap_err()
+                .to_string()
+        };
+        let typo = parse("[[rules]]\nrule = \"allowed_sizes\"\ncatgory = \"creature\"\nsizes = [32]\n");
+        assert!(typo.contains("unknown field `catgory`"), "{}", typo);
+        let typo = parse("[[rules]]\nrule = \"non_directional\"\ncatgory = \"effect\"\n");
+        assert!(typo.contains("unknown field `catgory`"), "{}", typo);
+        let empty = parse("[[rules]]\nrule = \"allowed_sizes\"\nsizes = []\n");
+        assert!(
+            empty.contains("`sizes` of rule `allowed_sizes` must not be empty"),
+            "{}",
+            empty
+        );
+    }
+
+    #[test]
+    fn test_allowed_sizes_points_at_the_dimension_out_of_the_list() {
+        let file: AppearancesFile = serde_json::from_value(serde_json::json!({
+            "version": 2,
+            "appearances": [
+                { "id": 1, "name": "tall", "width": 32, "height": 48, "framegroups": [] },
+                { "id": 2, "name": "wide", "width": 48, "height": 32, "framegroups": [] },
+                { "id": 3, "name": "banner", "size": 32, "height": 48, "framegroups": [] },
+                { "id": 4, "name": "boulder", "size": 48, "height": 32, "framegroups": [] }
+            ]
+        }))
+        .unwrap();
+        let config: LintConfig = serde_json::from_value(serde_json::json!({
+            "rules": [{ "rule": "allowed_sizes", "sizes": [32] }]
+        }))
+        .unwrap();
+
+        let paths: Vec<Option<String>> = lint_appearances(&file, &config)
+            .into_iter()
+            .map(|diagnostic| diagnostic.context.path)
+            .collect();
+        assert_eq!(
+            paths,
+            [
+                Some("appearances[0].height".to_string()),
+                Some("appearances[1].width".to_string()),
+                Some("appearances[2].height".to_string()),
+                Some("appearances[3].size".to_string()),
+            ]
+        );
+    }
+}
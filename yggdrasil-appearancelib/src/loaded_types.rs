@@ -1,29 +1,101 @@
-use crate::types::{Direction, Offset};
-use std::collections::HashMap;
+use crate::types::{
+    Category, CollisionShape, Color, Direction, Footprint, FrameEvent, FrameGroupLight, Light, Metadata, Offset,
+    PlaybackMode, Properties, StartFrame,
+};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::ops::Bound;
 
 /// Appearance carregada do arquivo .dat
 #[derive(Default, Debug, Clone)]
 pub struct LoadedAppearance {
-    pub id:          u32,
-    pub name:        String,
-    pub offset:      Offset,
-    pub size:        u32,
-    pub framegroups: Vec<LoadedFrameGroup>,
+    pub id:            u32,
+    pub name:          String,
+    /// Grupos para busca com `AppearanceDatabase::find_by_tag`
+    pub tags:          Vec<String>,
+    pub offset:        Offset,
+    /// Largura da célula em pixels
+    pub width:         u32,
+    /// Altura da célula em pixels
+    pub height:        u32,
+    /// Área ocupada no mapa, em tiles
+    pub footprint:     Footprint,
+    /// Categoria (None = não classificada)
+    pub category:      Option<Category>,
+    /// Propriedades de gameplay
+    pub properties:    Properties,
+    /// Dados livres do projeto
+    pub metadata:      Metadata,
+    /// Luz emitida (padrão para todos os framegroups)
+    pub light:         Option<Light>,
+    /// Cor representativa no minimapa
+    pub minimap_color: Option<Color>,
+    pub framegroups:   Vec<LoadedFrameGroup>,
 }
 
 /// FrameGroup carregado
 #[derive(Debug, Clone)]
 pub struct LoadedFrameGroup {
-    pub name:       String,
-    pub animations: HashMap<Option<Direction>, LoadedAnimation>,
+    pub name:          String,
+    pub animations:    HashMap<Option<Direction>, LoadedAnimation>,
+    /// Framegroup seguinte quando a animação termina
+    pub next:          Option<String>,
+    /// Se pode ser interrompido antes de terminar
+    pub interruptible: bool,
+    /// Framegroup usado quando este não tem animação para a direção pedida
+    pub fallback:      Option<String>,
+    /// Formas de colisão válidas em todas as direções
+    pub shapes:        Vec<CollisionShape>,
+    /// Dados livres do projeto
+    pub metadata:      Metadata,
+    /// Luz deste estado (sobrescreve ou apaga a da appearance)
+    pub light:         Option<FrameGroupLight>,
 }
 
 /// Animação carregada com lista de sprite IDs
 #[derive(Debug, Clone)]
 pub struct LoadedAnimation {
-    pub sprite_ids: Vec<u32>,
-    pub duration:   u32,
-    pub looped:     bool,
+    pub sprite_ids:    Vec<u32>,
+    /// Duração total de um ciclo em milissegundos (soma das durações mínimas
+    /// de `frame_timings`; 0 = estático)
+    pub duration:      u32,
+    /// Temporização de cada frame, na mesma ordem de `sprite_ids`
+    pub frame_timings: Vec<FrameTiming>,
+    /// True para `Loop` e `PingPong`
+    pub looped:        bool,
+    /// Modo de reprodução
+    pub playback:      PlaybackMode,
+    /// Número de ciclos antes de terminar (None = infinito)
+    pub loop_count:    Option<u32>,
+    /// Política de frame inicial
+    pub start_frame:   StartFrame,
+    /// Anima em sincronia com o relógio global (ver `frame_at`)
+    pub synchronized:  bool,
+    /// Eventos disparados ao entrar em frames específicos
+    pub events:        Vec<FrameEvent>,
+    /// Pontos de ancoragem por nome: um ponto por frame, ou um único ponto
+    /// válido para todos os frames
+    pub attachments:   HashMap<String, Vec<Offset>>,
+    /// Formas de colisão desta direção
+    pub shapes:        Vec<CollisionShape>,
+    /// Dados livres do projeto
+    pub metadata:      Metadata,
+}
+
+/// Temporização de um frame em milissegundos
+///
+/// Para durações fixas `min == max`; caso contrário a duração deve ser
+/// sorteada entre `min` e `max` (inclusive) a cada exibição do frame.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FrameTiming {
+    pub min: u32,
+    pub max: u32,
+}
+
+impl FrameTiming {
+    /// Retorna true se a duração do frame é sorteada
+    pub fn is_random(&self) -> bool {
+        self.min != self.max
+    }
 }
 
 /// Sprite carregada (pixels descompactados)
@@ -38,8 +110,13 @@ pub struct LoadedSprite {
 /// Database completa de appearances
 #[derive(Default, Debug, Clone)]
 pub struct AppearanceDatabase {
-    pub version:     u32,
-    pub appearances: HashMap<u32, LoadedAppearance>,
+    pub version: u32,
+    /// Appearances por ID (privado para manter os índices sincronizados)
+    appearances: HashMap<u32, LoadedAppearance>,
+    /// Índice nome → ID (ordenado, para busca por prefixo)
+    names:       BTreeMap<String, u32>,
+    /// Índice tag → IDs
+    tags:        HashMap<String, BTreeSet<u32>>,
 }
 
 impl AppearanceDatabase {
@@ -47,25 +124,94 @@ impl AppearanceDatabase {
     pub fn new(version: u32) -> Self {
         Self {
             version,
-            appearances: HashMap::new(),
+            ..Default::default()
         }
     }
 
-    /// Adiciona uma appearance ao database
+    /// Adiciona uma appearance ao database (substitui a de mesmo ID)
     pub fn add_appearance(&mut self, appearance: LoadedAppearance) {
+        if let Some(previous) = self.appearances.remove(&appearance.id) {
+            self.unindex(&previous);
+        }
+
+        self.names.insert(appearance.name.clone(), appearance.id);
+        for tag in &appearance.tags {
+            self.tags.entry(tag.clone()).or_default().insert(appearance.id);
+        }
+
         self.appearances.insert(appearance.id, appearance);
     }
 
+    /// Remove uma appearance do database e dos índices
+    pub fn remove_appearance(&mut self, id: u32) -> Option<LoadedAppearance> {
+        let appearance = self.appearances.remove(&id)?;
+        self.unindex(&appearance);
+        Some(appearance)
+    }
+
+    /// Remove uma appearance dos índices de nome e tag
+    fn unindex(&mut self, appearance: &LoadedAppearance) {
+        if self.names.get(&appearance.name) == Some(&appearance.id) {
+            self.names.remove(&appearance.name);
+        }
+
+        for tag in &appearance.tags {
+            if let Some(ids) = self.tags.get_mut(tag) {
+                ids.remove(&appearance.id);
+                if ids.is_empty() {
+                    self.tags.remove(tag);
+                }
+            }
+        }
+    }
+
     /// Busca uma appearance por ID
-    pub fn get_appearance(&self, id: u32) -> Option<&LoadedAppearance> {
+    pub fn get(&self, id: u32) -> Option<&LoadedAppearance> {
         self.appearances.get(&id)
     }
 
-    /// Retorna todas as appearances
-    pub fn all_appearances(&self) -> impl Iterator<Item = &LoadedAppearance> {
+    /// Busca uma appearance por ID (mesmo que `get`)
+    pub fn get_appearance(&self, id: u32) -> Option<&LoadedAppearance> {
+        self.get(id)
+    }
+
+    /// Busca uma appearance pelo nome exato
+    pub fn get_by_name(&self, name: &str) -> Option<&LoadedAppearance> {
+        self.names.get(name).and_then(|id| self.appearances.get(id))
+    }
+
+    /// Retorna as appearances com uma tag, ordenadas por ID
+    pub fn find_by_tag<'a>(&'a self, tag: &str) -> impl Iterator<Item = &'a LoadedAppearance> + 'a {
+        self.tags
+            .get(tag)
+            .into_iter()
+            .flatten()
+            .filter_map(|id| self.appearances.get(id))
+    }
+
+    /// Retorna as appearances cujo nome começa com `prefix`, ordenadas por nome
+    pub fn find_by_name_prefix<'a>(&'a self, prefix: &'a str) -> impl Iterator<Item = &'a LoadedAppearance> + 'a {
+        self.names
+            .range::<str, _>((Bound::Included(prefix), Bound::Unbounded))
+            .take_while(move |(name, _)| name.starts_with(prefix))
+            .filter_map(|(_, id)| self.appearances.get(id))
+    }
+
+    /// Retorna todas as tags conhecidas
+    pub fn all_tags(&self) -> impl Iterator<Item = &String> {
+        self.tags.keys()
+    }
+
+    /// Itera sobre todas as appearances, sem ordem definida
+    pub fn iter(&self) -> impl Iterator<Item = &LoadedAppearance> {
         self.appearances.values()
     }
 
+    /// Retorna todas as appearances (mesmo que `iter`)
+    pub fn all_appearances(&self) -> impl Iterator<Item = &LoadedAppearance> {
+        self.iter()
+    }
+
     /// Retorna o número de appearances carregadas
     pub fn count(&self) -> usize {
         self.appearances.len()
@@ -87,6 +233,139 @@ impl LoadedAppearance {
     pub fn framegroup_names(&self) -> impl Iterator<Item = &String> {
         self.framegroups.iter().map(|fg| &fg.name)
     }
+
+    /// Retorna a luz ativa em um framegroup
+    ///
+    /// A luz do framegroup tem prioridade e `{ "enabled": false }` apaga a
+    /// luz; sem ela vale a da appearance. Framegroups inexistentes também usam
+    /// a luz da appearance.
+    pub fn light_for(&self, framegroup: &str) -> Option<&Light> {
+        let framegroup_light = self.get_framegroup(framegroup).and_then(|fg| fg.light.as_ref());
+        FrameGroupLight::apply(framegroup_light, self.light.as_ref())
+    }
+
+    /// Busca um ponto de ancoragem por framegroup, direção e frame
+    pub fn attachment(
+        &self, framegroup: &str, direction: Option<Direction>, name: &str, frame: usize,
+    ) -> Option<Offset> {
+        self.get_framegroup(framegroup)?
+            .get_animation_or_default(direction)?
+            .attachment(name, frame)
+    }
+
+    /// Retorna as formas de colisão de um framegroup, direção e frame
+    ///
+    /// Inclui as formas do framegroup (todas as direções) e as da animação
+    /// da direção (com fallback para a animação sem direção). Não depende dos
+    /// pixels, então funciona com `load_database_only` no servidor.
+    pub fn shapes(&self, framegroup: &str, direction: Option<Direction>, frame: usize) -> Vec<&CollisionShape> {
+        let Some(framegroup) = self.get_framegroup(framegroup) else {
+            return Vec::new();
+        };
+
+        let animation_shapes = framegroup
+            .get_animation_or_default(direction)
+            .map(|animation| animation.shapes.as_slice())
+            .unwrap_or_default();
+
+        framegroup
+            .shapes
+            .iter()
+            .chain(animation_shapes)
+            .filter(|shape| shape.applies_to(frame))
+            .collect()
+    }
+}
+
+impl LoadedAnimation {
+    /// Retorna a temporização de um frame (sem entrada, `duration` dividida
+    /// igualmente entre os frames)
+    pub fn frame_timing(&self, frame: usize) -> FrameTiming {
+        self.frame_timings.get(frame).copied().unwrap_or_else(|| {
+            let duration = self.duration / self.sprite_ids.len().max(1) as u32;
+            FrameTiming {
+                min: duration,
+                max: duration,
+            }
+        })
+    }
+
+    /// Retorna true se nenhum frame tem duração (a animação nunca avança)
+    pub fn is_static(&self) -> bool {
+        (0..self.sprite_ids.len()).all(|frame| self.frame_timing(frame).max == 0)
+    }
+
+    /// Calcula o frame atual a partir de um relógio global compartilhado
+    ///
+    /// Todas as instâncias que usam o mesmo `world_time_ms` exibem o mesmo
+    /// frame, sem estado por instância (tiles de água, lava, etc.). Durações
+    /// aleatórias usam o valor mínimo e `start_frame` é ignorado.
+    pub fn frame_at(&self, world_time_ms: u64) -> usize {
+        let last = self.sprite_ids.len().saturating_sub(1);
+        let duration = |frame: usize| self.frame_timing(frame).min as u64;
+
+        // Um ciclo é a ida (0..=last); o ping-pong soma a volta sem repetir as pontas
+        let forward: u64 = (0..=last).map(duration).sum();
+        let cycle = match self.playback {
+            PlaybackMode::PingPong if last > 0 => 2 * forward - duration(0) - duration(last),
+            _ => forward,
+        };
+        if cycle == 0 {
+            return 0;
+        }
+
+        // Animações finitas param no frame final após o último ciclo
+        let cycles_to_play = match self.playback {
+            PlaybackMode::Once | PlaybackMode::OnceAndHold => Some(1),
+            PlaybackMode::Loop | PlaybackMode::PingPong => self.loop_count.map(u64::from),
+        };
+        if let Some(cycles) = cycles_to_play
+            && world_time_ms >= cycle.saturating_mul(cycles)
+        {
+            return match self.playback {
+                PlaybackMode::OnceAndHold | PlaybackMode::Loop => last,
+                PlaybackMode::Once | PlaybackMode::PingPong => 0,
+            };
+        }
+
+        // Percorre a ida e, no ping-pong, a volta (last - 1 até 1)
+        let mut elapsed = world_time_ms % cycle;
+        for frame in (0..=last).chain((1..last).rev()) {
+            if elapsed < duration(frame) {
+                return frame;
+            }
+            elapsed -= duration(frame);
+        }
+
+        last
+    }
+
+    /// Busca um ponto de ancoragem para um frame
+    pub fn attachment(&self, name: &str, frame: usize) -> Option<Offset> {
+        match self.attachments.get(name)?.as_slice() {
+            [point] => Some(*point),
+            points => points.get(frame).copied(),
+        }
+    }
+
+    /// Retorna os eventos de um frame
+    pub fn events_at(&self, frame: usize) -> impl Iterator<Item = &FrameEvent> {
+        self.events.iter().filter(move |event| event.frame as usize == frame)
+    }
+
+    /// Retorna o sprite ID do frame atual segundo o relógio global
+    pub fn sprite_id_at(&self, world_time_ms: u64) -> Option<u32> {
+        self.sprite_ids.get(self.frame_at(world_time_ms)).copied()
+    }
+
+    /// Retorna a duração total de um ciclo da animação (min, max) em milissegundos
+    ///
+    /// A soma é feita em u64: com durações aleatórias grandes ela não cabe em u32.
+    pub fn total_duration(&self) -> (u64, u64) {
+        (0..self.sprite_ids.len())
+            .map(|frame| self.frame_timing(frame))
+            .fold((0, 0), |(min, max), timing| (min + timing.min as u64, max + timing.max as u64))
+    }
 }
 
 impl LoadedFrameGroup {
@@ -100,3 +379,259 @@ impl LoadedFrameGroup {
         self.animations.get(&direction).or_else(|| self.animations.get(&None))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{TempDir, compile_and_load};
+    use crate::types::{DisabledLight, Rect, ShapeKind};
+
+    fn appearance(id: u32, name: &str, tags: &[&str]) -> LoadedAppearance {
+        LoadedAppearance {
+            id,
+            name: name.to_string(),
+            tags: tags.iter().map(|tag| tag.to_string()).collect(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_name_and_tag_indexes() {
+        let mut database = AppearanceDatabase::new(2);
+        database.add_appearance(appearance(1, "warrior_male", &["humanoid"]));
+        database.add_appearance(appearance(2, "warrior_female", &["humanoid"]));
+        database.add_appearance(appearance(3, "wolf", &["beast"]));
+        database.add_appearance(appearance(4, "war_banner", &[]));
+
+        assert_eq!(database.get_by_name("wolf").map(|a| a.id), Some(3));
+        assert!(database.get_by_name("wol").is_none());
+
+        let humanoids: Vec<u32> = database.find_by_tag("humanoid").map(|a| a.id).collect();
+        assert_eq!(humanoids, vec![1, 2]);
+        assert_eq!(database.find_by_tag("undead").count(), 0);
+
+        let warriors: Vec<&str> = database
+            .find_by_name_prefix("warrior")
+            .map(|a| a.name.as_str())
+            .collect();
+        assert_eq!(warriors, vec!["warrior_female", "warrior_male"]);
+        assert_eq!(database.find_by_name_prefix("war").count(), 3);
+
+        // Substituir uma appearance atualiza os índices
+        database.add_appearance(appearance(3, "dire_wolf", &["beast", "boss"]));
+        assert!(database.get_by_name("wolf").is_none());
+        assert_eq!(database.get_by_name("dire_wolf").map(|a| a.id), Some(3));
+        assert_eq!(database.find_by_tag("boss").count(), 1);
+        assert_eq!(database.count(), 4);
+
+        // Remover também limpa os índices
+        assert_eq!(database.remove_appearance(3).map(|a| a.id), Some(3));
+        assert!(database.get(3).is_none() && database.get_by_name("dire_wolf").is_none());
+        assert_eq!(database.find_by_tag("beast").count(), 0);
+        assert_eq!(database.all_tags().count(), 1);
+        assert_eq!(database.iter().count(), 3);
+    }
+
+    #[test]
+    fn test_collision_shapes_by_framegroup_direction_and_frame() {
+        let root = TempDir::new("loaded-shapes");
+        image::RgbaImage::new(12, 8).save(root.join("attack.png")).unwrap();
+
+        let database = compile_and_load(
+            &root,
+            r#"{
+                "version": 2,
+                "appearances": [{
+                    "id": 1, "name": "orc", "size": 4,
+                    "framegroups": [{
+                        "name": "attack", "spritesheet": "attack.png",
+                        "shapes": [{ "kind": "hurtbox", "x": 0, "y": 1, "width": 4, "height": 3 }],
+                        "animations": {
+                            "north": {
+                                "frame_count": 3,
+                                "shapes": [{ "kind": "hitbox", "x": 2, "y": 0, "width": 2, "height": 2, "frame": 2 }]
+                            },
+                            "south": { "frame_count": 3 }
+                        }
+                    }]
+                }]
+            }"#,
+        );
+        let orc = database.get(1).unwrap();
+
+        let kinds = |direction, frame| -> Vec<ShapeKind> {
+            orc.shapes("attack", direction, frame).iter().map(|shape| shape.kind).collect()
+        };
+        assert_eq!(kinds(Some(Direction::North), 2), [ShapeKind::Hurtbox, ShapeKind::Hitbox]);
+        assert_eq!(kinds(Some(Direction::North), 1), [ShapeKind::Hurtbox]);
+        assert_eq!(kinds(Some(Direction::South), 2), [ShapeKind::Hurtbox]);
+        assert!(orc.shapes("idle", None, 0).is_empty());
+
+        let hurtbox = orc.shapes("attack", None, 0)[0];
+        assert!(hurtbox.rect.contains(3, 3));
+        assert!(!hurtbox.rect.contains(4, 3) && !hurtbox.rect.contains(3, 0));
+
+        // Larguras e offsets grandes não estouram a soma
+        let huge = Rect {
+            x:      i32::MAX - 1,
+            y:      0,
+            width:  u32::MAX,
+            height: 1,
+        };
+        assert!(huge.contains(i32::MAX, 0));
+        assert!(!huge.contains(i32::MIN, 0));
+    }
+
+    #[test]
+    fn test_categories_and_properties_are_loaded() {
+        let root = TempDir::new("loaded-properties");
+        let database = compile_and_load(
+            &root,
+            r#"{
+                "version": 2,
+                "appearances": [
+                    {
+                        "id": 1, "name": "grass", "size": 32, "category": "ground",
+                        "properties": { "ground_speed": 150 }, "framegroups": []
+                    },
+                    {
+                        "id": 2, "name": "gold_coin", "size": 32, "category": "item",
+                        "properties": { "pickupable": true, "stackable": true }, "framegroups": []
+                    },
+                    { "id": 3, "name": "wall", "size": 32, "properties": { "blocking": true }, "framegroups": [] },
+                    { "id": 4, "name": "orc", "size": 32, "category": "creature", "framegroups": [] },
+                    { "id": 5, "name": "fire", "size": 32, "category": "effect", "framegroups": [] },
+                    { "id": 6, "name": "arrow", "size": 32, "category": "missile", "framegroups": [] }
+                ]
+            }"#,
+        );
+
+        let grass = database.get_by_name("grass").unwrap();
+        assert_eq!(grass.category, Some(Category::Ground));
+        assert_eq!(grass.properties.ground_speed, Some(150));
+        assert!(!grass.properties.blocking);
+
+        let coin = database.get_by_name("gold_coin").unwrap();
+        assert_eq!(coin.category, Some(Category::Item));
+        assert!(coin.properties.pickupable && coin.properties.stackable && !coin.properties.blocking);
+
+        let wall = database.get_by_name("wall").unwrap();
+        assert_eq!(wall.category, None);
+        assert!(wall.properties.blocking && !wall.properties.pickupable);
+        assert_eq!(wall.properties.ground_speed, None);
+
+        let categories: Vec<Option<Category>> = (4..=6).map(|id| database.get(id).unwrap().category).collect();
+        assert_eq!(
+            categories,
+            [Some(Category::Creature), Some(Category::Effect), Some(Category::Missile)]
+        );
+        assert_eq!(database.get(4).unwrap().properties, Properties::default());
+    }
+
+    #[test]
+    fn test_metadata_is_loaded_at_every_level() {
+        #[derive(serde::Deserialize, Debug, PartialEq)]
+        struct Tint {
+            r: u8,
+            g: u8,
+            b: u8,
+        }
+
+        let root = TempDir::new("loaded-metadata");
+        image::RgbaImage::new(4, 4).save(root.join("idle.png")).unwrap();
+
+        let database = compile_and_load(
+            &root,
+            r#"{
+                "version": 2,
+                "appearances": [{
+                    "id": 1, "name": "orc", "size": 4,
+                    "metadata": {
+                        "editor_folder": "creatures/orcs",
+                        "rarity": 3,
+                        "hidden": false,
+                        "tint": { "r": 255, "g": 204, "b": 0 }
+                    },
+                    "framegroups": [{
+                        "name": "idle", "spritesheet": "idle.png",
+                        "metadata": { "speed": 1.5 },
+                        "animations": { "null": { "frame_count": 1, "metadata": { "sound": "growl" } } }
+                    }]
+                }]
+            }"#,
+        );
+        let orc = database.get(1).unwrap();
+
+        let metadata = &orc.metadata;
+        assert_eq!(metadata.get_str("editor_folder"), Some("creatures/orcs"));
+        assert_eq!(metadata.get_i64("rarity"), Some(3));
+        assert_eq!(metadata.get_bool("hidden"), Some(false));
+        assert_eq!(metadata.get_str("rarity"), None);
+        assert_eq!(
+            metadata.get_as::<Tint>("tint").unwrap(),
+            Some(Tint { r: 255, g: 204, b: 0 })
+        );
+        assert!(metadata.get_as::<Tint>("rarity").is_err());
+        assert_eq!(metadata.value()["tint"]["g"], 204);
+        assert!(metadata.get("missing").is_none());
+
+        let idle = orc.get_framegroup("idle").unwrap();
+        assert_eq!(idle.metadata.get_f64("speed"), Some(1.5));
+        let animation = idle.get_animation(None).unwrap();
+        assert_eq!(animation.metadata.get_str("sound"), Some("growl"));
+
+        // Metadata precisa ser um objeto
+        assert!(serde_json::from_str::<Metadata>("[1]").is_err());
+    }
+
+    #[test]
+    fn test_light_for_framegroups() {
+        let root = TempDir::new("loaded-light");
+        image::RgbaImage::new(4, 4).save(root.join("torch.png")).unwrap();
+
+        let database = compile_and_load(
+            &root,
+            r##"{
+                "version": 2,
+                "appearances": [{
+                    "id": 10, "name": "torch", "size": 4,
+                    "light": { "intensity": 1.0, "color": "#ff8800", "radius": 4, "flicker": { "period": 400, "amount": 0.5 } },
+                    "framegroups": [
+                        { "name": "lit", "spritesheet": "torch.png", "animations": { "null": { "frame_count": 1 } } },
+                        {
+                            "name": "dim", "spritesheet": "torch.png",
+                            "light": { "intensity": 0.25, "color": "#000000", "radius": 0 },
+                            "animations": { "null": { "frame_count": 1 } }
+                        },
+                        {
+                            "name": "out", "spritesheet": "torch.png",
+                            "light": { "enabled": false },
+                            "animations": { "null": { "frame_count": 1 } }
+                        }
+                    ]
+                }]
+            }"##,
+        );
+        let torch = database.get(10).unwrap();
+
+        let light = torch.light_for("lit").unwrap();
+        assert_eq!(light.color, Color { r: 255, g: 136, b: 0 });
+        assert_eq!(String::from(light.color), "#ff8800");
+        assert_eq!(light.intensity_at(0), 1.0);
+        assert!((light.intensity_at(200) - 0.5).abs() < 1e-5);
+        assert_eq!(torch.light_for("missing"), Some(light));
+
+        let dim = torch.light_for("dim").unwrap();
+        assert_eq!((dim.radius, dim.intensity, dim.flicker), (0, 0.25, None));
+        assert_eq!(torch.light_for("out"), None);
+        assert_eq!(
+            torch.get_framegroup("out").unwrap().light,
+            Some(FrameGroupLight::Disabled(DisabledLight { enabled: false }))
+        );
+
+        // O marcador não aceita outros campos e a cor precisa de seis dígitos hexadecimais
+        assert!(serde_json::from_str::<FrameGroupLight>(r#"{ "enabled": false, "radius": 2 }"#).is_err());
+        assert!(Color::try_from("#ff88".to_string()).is_err());
+        assert!(Color::try_from("#gg8800".to_string()).is_err());
+    }
+}
@@ -1,6 +1,13 @@
+use crate::compiler::{DAT_FORMAT_VERSION, DAT_MAGIC};
 use crate::error::{AppearanceError, Result};
-use crate::loaded_types::{AppearanceDatabase, LoadedAnimation, LoadedAppearance, LoadedFrameGroup, LoadedSprite};
-use crate::types::{Direction, Offset};
+use crate::loaded_types::{
+    AppearanceDatabase, FrameTiming, LoadedAnimation, LoadedAppearance, LoadedFrameGroup, LoadedSprite,
+};
+use crate::mask::SpriteMask;
+use crate::types::{
+    Category, CollisionShape, Color, Direction, DisabledLight, Flicker, Footprint, FrameEvent, FrameGroupLight, Light,
+    Metadata, Offset, PlaybackMode, Properties, Rect, ShapeKind, StartFrame,
+};
 use byteorder::{LittleEndian, ReadBytesExt};
 use flate2::read::GzDecoder;
 use std::collections::HashMap;
@@ -13,6 +20,8 @@ use std::path::{Path, PathBuf};
 pub struct AppearanceLoader {
     base_path:    PathBuf,
     sprite_cache: HashMap<u32, LoadedSprite>,
+    /// Máscaras de alpha (carregadas do masks.dat na primeira consulta)
+    masks:        Option<HashMap<u32, SpriteMask>>,
 }
 
 impl AppearanceLoader {
@@ -21,6 +30,7 @@ impl AppearanceLoader {
         Self {
             base_path:    base_path.as_ref().to_path_buf(),
             sprite_cache: HashMap::new(),
+            masks:        None,
         }
     }
 
@@ -30,7 +40,23 @@ impl AppearanceLoader {
         let data = fs::read(&dat_path)?;
         let mut cursor = Cursor::new(data);
 
-        // Lê header
+        // Lê header: assinatura e versão do formato antes de qualquer registro
+        let mut magic = [0u8; 4];
+        if cursor.read_exact(&mut magic).is_err() || magic != DAT_MAGIC {
+            return Err(AppearanceError::InvalidMagic {
+                path: dat_path.display().to_string(),
+            });
+        }
+
+        let format_version = cursor.read_u32::<LittleEndian>()?;
+        if format_version != DAT_FORMAT_VERSION {
+            return Err(AppearanceError::UnsupportedFormat {
+                path:     dat_path.display().to_string(),
+                found:    format_version,
+                expected: DAT_FORMAT_VERSION,
+            });
+        }
+
         let version = cursor.read_u32::<LittleEndian>()?;
         let appearance_count = cursor.read_u32::<LittleEndian>()?;
 
@@ -53,6 +79,14 @@ impl AppearanceLoader {
         // Nome
         let name = read_string(cursor)?;
 
+        // Tags
+        let tag_count = cursor.read_u32::<LittleEndian>()?;
+        let mut tags = Vec::new();
+
+        for _ in 0..tag_count {
+            tags.push(read_string(cursor)?);
+        }
+
         // Offset
         let offset_x = cursor.read_i32::<LittleEndian>()?;
         let offset_y = cursor.read_i32::<LittleEndian>()?;
@@ -61,8 +95,35 @@ impl AppearanceLoader {
             y: offset_y,
         };
 
-        // Size
-        let size = cursor.read_u32::<LittleEndian>()?;
+        // Dimensões da célula
+        let width = cursor.read_u32::<LittleEndian>()?;
+        let height = cursor.read_u32::<LittleEndian>()?;
+
+        // Footprint em tiles
+        let footprint = Footprint {
+            width:  cursor.read_u32::<LittleEndian>()?,
+            height: cursor.read_u32::<LittleEndian>()?,
+        };
+
+        // Categoria e propriedades de gameplay
+        let category = u8_to_category(cursor.read_u8()?);
+        let flags = cursor.read_u32::<LittleEndian>()?;
+        let ground_speed = match cursor.read_u8()? {
+            1 => Some(cursor.read_u32::<LittleEndian>()?),
+            _ => None,
+        };
+        let properties = Properties {
+            blocking:     flags & Properties::BLOCKING != 0,
+            pickupable:   flags & Properties::PICKUPABLE != 0,
+            stackable:    flags & Properties::STACKABLE != 0,
+            ground_speed,
+        };
+
+        // Metadata livre
+        let metadata = read_metadata(cursor)?;
+
+        // Luz
+        let light = read_light(cursor)?;
 
         // FrameGroups
         let framegroup_count = cursor.read_u32::<LittleEndian>()?;
@@ -73,11 +134,29 @@ impl AppearanceLoader {
             framegroups.push(framegroup);
         }
 
+        // Cor do minimapa
+        let minimap_color = match cursor.read_u8()? {
+            1 => Some(Color {
+                r: cursor.read_u8()?,
+                g: cursor.read_u8()?,
+                b: cursor.read_u8()?,
+            }),
+            _ => None,
+        };
+
         Ok(LoadedAppearance {
             id,
             name,
+            tags,
             offset,
-            size,
+            width,
+            height,
+            footprint,
+            category,
+            properties,
+            metadata,
+            light,
+            minimap_color,
             framegroups,
         })
     }
@@ -87,6 +166,20 @@ impl AppearanceLoader {
         // Nome do framegroup
         let name = read_string(cursor)?;
 
+        // Transições
+        let next = read_optional_string(cursor)?;
+        let interruptible = cursor.read_u8()? == 1;
+        let fallback = read_optional_string(cursor)?;
+
+        // Formas de colisão do framegroup
+        let shapes = read_shapes(cursor)?;
+
+        // Metadata livre do framegroup
+        let metadata = read_metadata(cursor)?;
+
+        // Luz do framegroup
+        let light = read_framegroup_light(cursor)?;
+
         // Número de animações (direções)
         let animation_count = cursor.read_u32::<LittleEndian>()?;
         let mut animations = HashMap::new();
@@ -103,23 +196,99 @@ impl AppearanceLoader {
 
             // Lê o número de sprite IDs
             let sprite_id_count = cursor.read_u32::<LittleEndian>()?;
-            let mut sprite_ids = Vec::with_capacity(sprite_id_count as usize);
+            let mut sprite_ids = Vec::new();
 
             for _ in 0..sprite_id_count {
                 let sprite_id = cursor.read_u32::<LittleEndian>()?;
                 sprite_ids.push(sprite_id);
             }
 
-            // Lê a duração
+            // Lê a duração total de um ciclo
             let duration = cursor.read_u32::<LittleEndian>()?;
 
+            // Lê a temporização de cada frame
+            let frame_timing_count = cursor.read_u32::<LittleEndian>()?;
+            let mut frame_timings = Vec::new();
+
+            for _ in 0..frame_timing_count {
+                frame_timings.push(FrameTiming {
+                    min: cursor.read_u32::<LittleEndian>()?,
+                    max: cursor.read_u32::<LittleEndian>()?,
+                });
+            }
+
             // Lê o flag looped (1 = true, 0 = false)
             let looped = cursor.read_u8()? == 1;
 
+            // Lê o modo de reprodução e o número de ciclos (0 = infinito)
+            let playback = u8_to_playback(cursor.read_u8()?);
+            let loop_count = match cursor.read_u32::<LittleEndian>()? {
+                0 => None,
+                count => Some(count),
+            };
+
+            // Lê a política de frame inicial
+            let start_kind = cursor.read_u8()?;
+            let start_index = cursor.read_u32::<LittleEndian>()?;
+            let start_frame = match start_kind {
+                1 => StartFrame::Random,
+                2 => StartFrame::Frame(start_index),
+                _ => StartFrame::First,
+            };
+
+            // Lê o flag synchronized
+            let synchronized = cursor.read_u8()? == 1;
+
+            // Lê os eventos de frame
+            let event_count = cursor.read_u32::<LittleEndian>()?;
+            let mut events = Vec::new();
+
+            for _ in 0..event_count {
+                events.push(FrameEvent {
+                    frame:   cursor.read_u32::<LittleEndian>()?,
+                    name:    read_string(cursor)?,
+                    payload: read_optional_string(cursor)?,
+                });
+            }
+
+            // Lê os pontos de ancoragem
+            let attachment_count = cursor.read_u32::<LittleEndian>()?;
+            let mut attachments = HashMap::new();
+
+            for _ in 0..attachment_count {
+                let attachment_name = read_string(cursor)?;
+                let point_count = cursor.read_u32::<LittleEndian>()?;
+                let mut points = Vec::new();
+
+                for _ in 0..point_count {
+                    points.push(Offset {
+                        x: cursor.read_i32::<LittleEndian>()?,
+                        y: cursor.read_i32::<LittleEndian>()?,
+                    });
+                }
+
+                attachments.insert(attachment_name, points);
+            }
+
+            // Lê as formas de colisão da direção
+            let shapes = read_shapes(cursor)?;
+
+            // Lê a metadata livre
+            let metadata = read_metadata(cursor)?;
+
             let animation = LoadedAnimation {
                 sprite_ids,
                 duration,
+                frame_timings,
                 looped,
+                playback,
+                loop_count,
+                start_frame,
+                synchronized,
+                events,
+                attachments,
+                shapes,
+                metadata,
             };
 
             animations.insert(direction, animation);
@@ -128,6 +297,12 @@ impl AppearanceLoader {
         Ok(LoadedFrameGroup {
             name,
             animations,
+            next,
+            interruptible,
+            fallback,
+            shapes,
+            metadata,
+            light,
         })
     }
 
@@ -164,6 +339,17 @@ impl AppearanceLoader {
         let height = cursor.read_u32::<LittleEndian>()?;
         let compressed_size = cursor.read_u32::<LittleEndian>()?;
 
+        // O tamanho vem do arquivo: confere antes de alocar
+        let remaining = cursor.get_ref().len() as u64 - cursor.position();
+        if u64::from(compressed_size) > remaining {
+            return Err(AppearanceError::InvalidData(format!(
+                "Sprite file '{}' is truncated: header declares {} bytes of pixels but only {} remain",
+                sprite_path.display(),
+                compressed_size,
+                remaining
+            )));
+        }
+
         // Lê pixels compactados
         let mut compressed_pixels = vec![0u8; compressed_size as usize];
         cursor.read_exact(&mut compressed_pixels)?;
@@ -224,6 +410,51 @@ impl AppearanceLoader {
     pub fn cache_size_bytes(&self) -> usize {
         self.sprite_cache.values().map(|sprite| sprite.pixels.len()).sum()
     }
+
+    /// Carrega o arquivo masks.dat (gerado com `CompileOptions::alpha_masks`)
+    pub fn load_masks(&mut self) -> Result<()> {
+        let masks_path = self.base_path.join("masks.dat");
+
+        if !masks_path.exists() {
+            return Err(AppearanceError::MasksNotFound {
+                path: masks_path.display().to_string(),
+            });
+        }
+
+        let mut cursor = Cursor::new(fs::read(&masks_path)?);
+        let mask_count = cursor.read_u32::<LittleEndian>()?;
+        let mut masks = HashMap::new();
+
+        for _ in 0..mask_count {
+            let sprite_id = cursor.read_u32::<LittleEndian>()?;
+            masks.insert(sprite_id, SpriteMask::read(&mut cursor)?);
+        }
+
+        self.masks = Some(masks);
+        Ok(())
+    }
+
+    /// Retorna a máscara de alpha de uma sprite, sem tocar nos pixels RGBA
+    pub fn get_mask(&mut self, sprite_id: u32) -> Result<&SpriteMask> {
+        if self.masks.is_none() {
+            self.load_masks()?;
+        }
+
+        self.masks
+            .as_ref()
+            .and_then(|masks| masks.get(&sprite_id))
+            .ok_or_else(|| AppearanceError::InvalidData(format!("No alpha mask for sprite {}", sprite_id)))
+    }
+
+    /// Retorna true se o pixel (x, y) da sprite é opaco
+    pub fn hit_test(&mut self, sprite_id: u32, x: i32, y: i32) -> Result<bool> {
+        Ok(self.get_mask(sprite_id)?.is_opaque(x, y))
+    }
+
+    /// Retorna o bounding box dos pixels opacos da sprite (None = transparente)
+    pub fn opaque_bounds(&mut self, sprite_id: u32) -> Result<Option<Rect>> {
+        Ok(self.get_mask(sprite_id)?.bounds)
+    }
 }
 
 /// Converte u8 para Direction
@@ -241,16 +472,132 @@ fn u8_to_direction(byte: u8) -> Direction {
     }
 }
 
+/// Lê uma lista de formas de colisão
+fn read_shapes<R: Read>(reader: &mut R) -> Result<Vec<CollisionShape>> {
+    let shape_count = reader.read_u32::<LittleEndian>()?;
+    let mut shapes = Vec::new();
+
+    for _ in 0..shape_count {
+        let kind = match reader.read_u8()? {
+            1 => ShapeKind::Hurtbox,
+            _ => ShapeKind::Hitbox,
+        };
+        let rect = Rect {
+            x:      reader.read_i32::<LittleEndian>()?,
+            y:      reader.read_i32::<LittleEndian>()?,
+            width:  reader.read_u32::<LittleEndian>()?,
+            height: reader.read_u32::<LittleEndian>()?,
+        };
+        let frame = match reader.read_u32::<LittleEndian>()? {
+            u32::MAX => None,
+            frame => Some(frame),
+        };
+        shapes.push(CollisionShape { kind, rect, frame });
+    }
+
+    Ok(shapes)
+}
+
+/// Converte u8 para Category (0 = sem categoria)
+fn u8_to_category(byte: u8) -> Option<Category> {
+    match byte {
+        1 => Some(Category::Item),
+        2 => Some(Category::Ground),
+        3 => Some(Category::Creature),
+        4 => Some(Category::Effect),
+        5 => Some(Category::Missile),
+        _ => None,
+    }
+}
+
+/// Converte u8 para PlaybackMode
+fn u8_to_playback(byte: u8) -> PlaybackMode {
+    match byte {
+        1 => PlaybackMode::Once,
+        2 => PlaybackMode::OnceAndHold,
+        3 => PlaybackMode::PingPong,
+        _ => PlaybackMode::Loop, // Default
+    }
+}
+
 /// Lê uma string do formato: length (u32) + bytes (UTF-8)
 fn read_string<R: Read>(reader: &mut R) -> Result<String> {
     let length = reader.read_u32::<LittleEndian>()?;
-    let mut bytes = vec![0u8; length as usize];
-    reader.read_exact(&mut bytes)?;
+
+    // Lê no máximo o tamanho declarado, sem alocá-lo antes de ver os bytes
+    let mut bytes = Vec::new();
+    reader.take(length as u64).read_to_end(&mut bytes)?;
+    if bytes.len() != length as usize {
+        return Err(AppearanceError::InvalidData(format!(
+            "Truncated string: expected {} bytes, got {}",
+            length,
+            bytes.len()
+        )));
+    }
+
     let s =
         String::from_utf8(bytes).map_err(|e| AppearanceError::InvalidData(format!("Invalid UTF-8 string: {}", e)))?;
     Ok(s)
 }
 
+/// Lê uma string opcional: flag (u8) + string se presente
+fn read_optional_string<R: Read>(reader: &mut R) -> Result<Option<String>> {
+    match reader.read_u8()? {
+        1 => Ok(Some(read_string(reader)?)),
+        _ => Ok(None),
+    }
+}
+
+/// Lê uma luz opcional: flag (u8) + dados se presente
+fn read_light<R: Read>(reader: &mut R) -> Result<Option<Light>> {
+    match reader.read_u8()? {
+        1 => Ok(Some(read_light_data(reader)?)),
+        _ => Ok(None),
+    }
+}
+
+/// Lê a luz de um framegroup: como `read_light`, com flag 2 = luz apagada
+fn read_framegroup_light<R: Read>(reader: &mut R) -> Result<Option<FrameGroupLight>> {
+    match reader.read_u8()? {
+        1 => Ok(Some(FrameGroupLight::Light(read_light_data(reader)?))),
+        2 => Ok(Some(FrameGroupLight::Disabled(DisabledLight { enabled: false }))),
+        _ => Ok(None),
+    }
+}
+
+/// Lê os dados de uma luz (depois do flag)
+fn read_light_data<R: Read>(reader: &mut R) -> Result<Light> {
+    let intensity = reader.read_f32::<LittleEndian>()?;
+    let color = Color {
+        r: reader.read_u8()?,
+        g: reader.read_u8()?,
+        b: reader.read_u8()?,
+    };
+    let radius = reader.read_u32::<LittleEndian>()?;
+    let flicker = match reader.read_u8()? {
+        1 => Some(Flicker {
+            period: reader.read_u32::<LittleEndian>()?,
+            amount: reader.read_f32::<LittleEndian>()?,
+        }),
+        _ => None,
+    };
+
+    Ok(Light {
+        intensity,
+        color,
+        radius,
+        flicker,
+    })
+}
+
+/// Lê a metadata (JSON compacto em string opcional)
+fn read_metadata<R: Read>(reader: &mut R) -> Result<Metadata> {
+    match read_optional_string(reader)? {
+        Some(json) => Ok(serde_json::from_str(&json)?),
+        None => Ok(Metadata::default()),
+    }
+}
+
 /// Função helper para carregar database + todos os sprites de uma vez
 pub fn load_all<P: AsRef<Path>>(base_path: P) -> Result<(AppearanceDatabase, AppearanceLoader)> {
     let mut loader = AppearanceLoader::new(base_path);
@@ -258,7 +605,7 @@ pub fn load_all<P: AsRef<Path>>(base_path: P) -> Result<(AppearanceDatabase, App
 
     // Pré-carrega todos os sprites
     let all_sprite_ids: Vec<u32> = database
-        .all_appearances()
+        .iter()
         .flat_map(|app| {
             app.framegroups
                 .iter()
@@ -283,6 +630,193 @@ pub fn load_database_only<P: AsRef<Path>>(base_path: P) -> Result<(AppearanceDat
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::test_support::TempDir;
+    use crate::types::AppearancesFile;
+    use crate::{CompileOptions, compile_appearances_with_options};
+
+    #[test]
+    fn test_compiled_database_round_trips() {
+        let root = TempDir::new("round-trip");
+        let output = root.join("compiled");
+        image::RgbaImage::new(8, 12).save(root.join("walk.png")).unwrap();
+        image::RgbaImage::new(4, 6).save(root.join("out.png")).unwrap();
+
+        let file: AppearancesFile = serde_json::from_str(
+            r##"{
+                "version": 7,
+                "appearances": [{
+                    "id": 42, "name": "bonfire", "tags": ["fire", "light"],
+                    "offset": { "x": -2, "y": 3 }, "width": 4, "height": 6,
+                    "footprint": { "width": 2, "height": 1 },
+                    "category": "ground",
+                    "properties": { "blocking": true, "stackable": true, "ground_speed": 150 },
+                    "metadata": { "burn": 5 },
+                    "light": { "intensity": 0.75, "color": "#ff8800", "radius": 3, "flicker": { "period": 200, "amount": 0.25 } },
+                    "minimap_color": "#102030",
+                    "framegroups": [{
+                        "name": "walk", "spritesheet": "walk.png",
+                        "next": "walk", "interruptible": false, "fallback": "walk",
+                        "shapes": [{ "kind": "hurtbox", "x": 0, "y": 1, "width": 4, "height": 5 }],
+                        "metadata": { "speed": "fast" },
+                        "light": { "intensity": 1.0, "color": "#ffffff", "radius": 1 },
+                        "animations": {
+                            "north": {
+                                "frame_count": 2, "duration": 80,
+                                "frame_durations": [100, { "min": 50, "max": 90 }],
+                                "playback": "ping_pong", "loop_count": 3,
+                                "start_frame": { "frame": 1 }, "synchronized": true,
+                                "events": [{ "frame": 1, "name": "step", "payload": "grass" }],
+                                "attachments": {
+                                    "hand": { "x": 1, "y": 2 },
+                                    "head": [{ "x": 0, "y": -1 }, { "x": 0, "y": -2 }]
+                                },
+                                "shapes": [{ "kind": "hitbox", "x": -1, "y": 0, "width": 2, "height": 2, "frame": 1 }],
+                                "metadata": { "sound": "walk" }
+                            },
+                            "south": { "frame_count": 2, "looped": false, "start_frame": "random" }
+                        }
+                    }, {
+                        "name": "out", "spritesheet": "out.png",
+                        "light": { "enabled": false },
+                        "animations": { "null": { "frame_count": 1 } }
+                    }]
+                }]
+            }"##,
+        )
+        .unwrap();
+        compile_appearances_with_options(&file, root.path(), &output, &CompileOptions::default()).unwrap();
+
+        let (database, mut loader) = load_database_only(&output).unwrap();
+        assert_eq!(database.version, 7);
+        assert_eq!(database.count(), 1);
+
+        let appearance = database.get(42).unwrap();
+        assert_eq!(appearance.name, "bonfire");
+        assert_eq!(appearance.tags, ["fire", "light"]);
+        assert_eq!(appearance.offset, Offset { x: -2, y: 3 });
+        assert_eq!((appearance.width, appearance.height), (4, 6));
+        assert_eq!(appearance.footprint, Footprint { width: 2, height: 1 });
+        assert_eq!(appearance.category, Some(Category::Ground));
+        assert_eq!(
+            appearance.properties,
+            Properties {
+                blocking:     true,
+                pickupable:   false,
+                stackable:    true,
+                ground_speed: Some(150),
+            }
+        );
+        assert_eq!(appearance.metadata.get_i64("burn"), Some(5));
+        assert_eq!(
+            appearance.light,
+            Some(Light {
+                intensity: 0.75,
+                color:     Color { r: 255, g: 136, b: 0 },
+                radius:    3,
+                flicker:   Some(Flicker {
+                    period: 200,
+                    amount: 0.25,
+                }),
+            })
+        );
+        assert_eq!(appearance.minimap_color, Some(Color { r: 16, g: 32, b: 48 }));
+
+        let walk = appearance.get_framegroup("walk").unwrap();
+        assert_eq!(walk.next.as_deref(), Some("walk"));
+        assert!(!walk.interruptible);
+        assert_eq!(walk.fallback.as_deref(), Some("walk"));
+        assert_eq!(walk.shapes, file.appearances[0].framegroups[0].shapes);
+        assert_eq!(walk.metadata.get_str("speed"), Some("fast"));
+        assert!(matches!(walk.light, Some(FrameGroupLight::Light(Light { radius: 1, .. }))));
+        assert_eq!(appearance.light_for("walk").map(|light| light.radius), Some(1));
+        assert_eq!(appearance.light_for("out"), None);
+        assert_eq!(appearance.light_for("missing").map(|light| light.radius), Some(3));
+        assert_eq!(walk.animations.len(), 2);
+
+        let north = walk.get_animation(Some(Direction::North)).unwrap();
+        assert_eq!(north.sprite_ids.len(), 2);
+        assert_eq!(north.duration, 150);
+        assert_eq!(
+            north.frame_timings,
+            [FrameTiming { min: 100, max: 100 }, FrameTiming { min: 50, max: 90 }]
+        );
+        assert!(north.looped);
+        assert_eq!(north.playback, PlaybackMode::PingPong);
+        assert_eq!(north.loop_count, Some(3));
+        assert_eq!(north.start_frame, StartFrame::Frame(1));
+        assert!(north.synchronized);
+        assert_eq!(
+            north.events,
+            [FrameEvent {
+                frame:   1,
+                name:    "step".to_string(),
+                payload: Some("grass".to_string()),
+            }]
+        );
+        assert_eq!(north.attachments["hand"], [Offset { x: 1, y: 2 }]);
+        assert_eq!(north.attachments["head"], [Offset { x: 0, y: -1 }, Offset { x: 0, y: -2 }]);
+        assert_eq!(
+            north.shapes,
+            [CollisionShape {
+                kind:  ShapeKind::Hitbox,
+                rect:  Rect {
+                    x:      -1,
+                    y:      0,
+                    width:  2,
+                    height: 2,
+                },
+                frame: Some(1),
+            }]
+        );
+        assert_eq!(north.metadata.get_str("sound"), Some("walk"));
+
+        let south = walk.get_animation(Some(Direction::South)).unwrap();
+        assert!(!south.looped);
+        assert_eq!(south.playback, PlaybackMode::Once);
+        assert_eq!(south.loop_count, None);
+        assert_eq!(south.start_frame, StartFrame::Random);
+        assert!(!south.synchronized);
+        assert!(south.events.is_empty() && south.attachments.is_empty() && south.metadata.is_empty());
+
+        let sprite = loader.load_sprite(north.sprite_ids[0]).unwrap();
+        assert_eq!((sprite.width, sprite.height, sprite.pixels.len()), (4, 6, 4 * 6 * 4));
+    }
+
+    #[test]
+    fn test_rejects_other_format_versions() {
+        let root = TempDir::new("dat-header");
+        let mut loader = AppearanceLoader::new(root.path());
+
+        root.write("appearances.dat", [0u8; 16]);
+        assert!(matches!(loader.load_database(), Err(AppearanceError::InvalidMagic { .. })));
+
+        let mut dat = DAT_MAGIC.to_vec();
+        dat.extend((DAT_FORMAT_VERSION + 1).to_le_bytes());
+        dat.extend([0u8; 8]);
+        root.write("appearances.dat", dat);
+        assert!(matches!(
+            loader.load_database(),
+            Err(AppearanceError::UnsupportedFormat { found, expected: DAT_FORMAT_VERSION, .. })
+                if found == DAT_FORMAT_VERSION + 1
+        ));
+    }
+
+    #[test]
+    fn test_truncated_counts_are_rejected_without_allocating() {
+        let root = TempDir::new("dat-counts");
+        let mut loader = AppearanceLoader::new(root.path());
+
+        // Uma appearance cujo número de tags (u32::MAX) não cabe no arquivo
+        let mut dat = DAT_MAGIC.to_vec();
+        for value in [DAT_FORMAT_VERSION, 1, 1, 42, 0, u32::MAX] {
+            dat.extend(value.to_le_bytes());
+        }
+        root.write("appearances.dat", &dat);
+        assert!(loader.load_database().is_err());
+
+        let mut cursor = Cursor::new(u32::MAX.to_le_bytes().to_vec());
+        assert!(read_string(&mut cursor).unwrap_err().to_string().contains("Truncated string"));
+    }
 
     #[test]
     fn test_read_string() {
@@ -0,0 +1,179 @@
+use crate::error::{AppearanceError, Result};
+use crate::types::Rect;
+use byteorder::{LittleEndian, ReadBytesExt, WriteBytesExt};
+use std::io::{Read, Write};
+
+/// Máscara de alpha de 1 bit de uma sprite
+///
+/// Permite testes de clique pixel-perfect e consultas de bounding box sem
+/// descompactar os pixels RGBA da sprite.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SpriteMask {
+    pub width:  u32,
+    pub height: u32,
+    /// Menor retângulo que contém todos os pixels opacos (None = totalmente transparente)
+    pub bounds: Option<Rect>,
+    /// Um bit por pixel, linha a linha (bit mais significativo primeiro)
+    pub bits:   Vec<u8>,
+}
+
+impl SpriteMask {
+    /// Gera a máscara a partir de pixels RGBA
+    ///
+    /// Um pixel é opaco quando seu alpha é maior que `alpha_threshold`.
+    pub fn from_rgba(width: u32, height: u32, pixels: &[u8], alpha_threshold: u8) -> Self {
+        let mut bits = vec![0u8; (width as usize * height as usize).div_ceil(8)];
+        let mut min = (u32::MAX, u32::MAX);
+        let mut max = (0u32, 0u32);
+
+        for (index, pixel) in pixels.chunks_exact(4).enumerate() {
+            if pixel[3] <= alpha_threshold {
+                continue;
+            }
+
+            bits[index / 8] |= 0x80 >> (index % 8);
+
+            let (x, y) = (index as u32 % width, index as u32 / width);
+            min = (min.0.min(x), min.1.min(y));
+            max = (max.0.max(x), max.1.max(y));
+        }
+
+        let bounds = (min.0 != u32::MAX).then(|| Rect {
+            x:      min.0 as i32,
+            y:      min.1 as i32,
+            width:  max.0 - min.0 + 1,
+            height: max.1 - min.1 + 1,
+        });
+
+        Self {
+            width,
+            height,
+            bounds,
+            bits,
+        }
+    }
+
+    /// Retorna true se o pixel (x, y) é opaco (false fora da sprite)
+    pub fn is_opaque(&self, x: i32, y: i32) -> bool {
+        if x < 0 || y < 0 || x as u32 >= self.width || y as u32 >= self.height {
+            return false;
+        }
+
+        let index = y as usize * self.width as usize + x as usize;
+        self.bits[index / 8] & (0x80 >> (index % 8)) != 0
+    }
+
+    /// Escreve a máscara no formato binário do masks.dat
+    pub(crate) fn write<W: Write>(&self, writer: &mut W) -> Result<()> {
+        writer.write_u32::<LittleEndian>(self.width)?;
+        writer.write_u32::<LittleEndian>(self.height)?;
+
+        match self.bounds {
+            Some(bounds) => {
+                writer.write_u8(1)?;
+                writer.write_i32::<LittleEndian>(bounds.x)?;
+                writer.write_i32::<LittleEndian>(bounds.y)?;
+                writer.write_u32::<LittleEndian>(bounds.width)?;
+                writer.write_u32::<LittleEndian>(bounds.height)?;
+            }
+            None => writer.write_u8(0)?,
+        }
+
+        writer.write_all(&self.bits)?;
+        Ok(())
+    }
+
+    /// Lê uma máscara no formato binário do masks.dat
+    pub(crate) fn read<R: Read>(reader: &mut R) -> Result<Self> {
+        let width = reader.read_u32::<LittleEndian>()?;
+        let height = reader.read_u32::<LittleEndian>()?;
+
+        let bounds = match reader.read_u8()? {
+            0 => None,
+            1 => Some(Rect {
+                x:      reader.read_i32::<LittleEndian>()?,
+                y:      reader.read_i32::<LittleEndian>()?,
+                width:  reader.read_u32::<LittleEndian>()?,
+                height: reader.read_u32::<LittleEndian>()?,
+            }),
+            flag => {
+                return Err(AppearanceError::InvalidData(format!(
+                    "Invalid mask bounds flag: {}",
+                    flag
+                )));
+            }
+        };
+
+        // Lê no máximo o tamanho declarado, sem alocá-lo antes de ver os bytes
+        let expected = (width as u64 * height as u64).div_ceil(8);
+        let mut bits = Vec::new();
+        reader.take(expected).read_to_end(&mut bits)?;
+
+        if bits.len() as u64 != expected {
+            return Err(AppearanceError::InvalidData(format!(
+                "Truncated alpha mask: expected {} bytes for {}x{}, got {}",
+                expected,
+                width,
+                height,
+                bits.len()
+            )));
+        }
+
+        Ok(Self {
+            width,
+            height,
+            bounds,
+            bits,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    #[test]
+    fn test_mask_hit_test_and_bounds() {
+        // 3x2: apenas (1,0) e (2,1) opacos
+        let mut pixels = vec![0u8; 3 * 2 * 4];
+        pixels[4 + 3] = 255;
+        pixels[(3 + 2) * 4 + 3] = 128;
+
+        let mask = SpriteMask::from_rgba(3, 2, &pixels, 0);
+        assert!(mask.is_opaque(1, 0));
+        assert!(mask.is_opaque(2, 1));
+        assert!(!mask.is_opaque(0, 0));
+        assert!(!mask.is_opaque(-1, 0));
+        assert!(!mask.is_opaque(3, 1));
+        assert_eq!(
+            mask.bounds,
+            Some(Rect {
+                x:      1,
+                y:      0,
+                width:  2,
+                height: 2,
+            })
+        );
+
+        let mut buffer = Vec::new();
+        mask.write(&mut buffer).unwrap();
+        assert_eq!(SpriteMask::read(&mut Cursor::new(buffer)).unwrap(), mask);
+
+        let strict = SpriteMask::from_rgba(3, 2, &pixels, 200);
+        assert!(!strict.is_opaque(2, 1));
+        assert_eq!(strict.bounds.map(|b| b.width), Some(1));
+    }
+
+    #[test]
+    fn test_truncated_mask_is_rejected() {
+        // Declara u32::MAX x u32::MAX sem nenhum byte de máscara
+        let mut buffer = Vec::new();
+        buffer.extend(u32::MAX.to_le_bytes());
+        buffer.extend(u32::MAX.to_le_bytes());
+        buffer.push(0);
+
+        let error = SpriteMask::read(&mut Cursor::new(buffer)).unwrap_err();
+        assert!(error.to_string().contains("Truncated alpha mask"));
+    }
+}
@@ -1,17 +1,197 @@
-use crate::error::Result;
+use crate::diagnostic::{ErrorContext, SourceLocation, json_spans, line_column, locate_in_file};
+use crate::error::{AppearanceError, Result};
+use crate::format::Format;
+use crate::include::{SOURCE_KEY, load_marked};
+use crate::template::resolve_templates;
 use crate::types::AppearancesFile;
-use std::path::Path;
+use serde_json::Value;
+use serde_path_to_error::Segment;
+use std::collections::{HashMap, VecDeque};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Carrega e parseia um arquivo de appearances em JSON, TOML, YAML ou RON
+///
+/// O formato é escolhido pela extensão. Os fragmentos de `include` (que podem
+/// usar outros formatos) são mesclados e os templates (`extends`) resolvidos
+/// antes da deserialização. Cada appearance guarda sua posição no arquivo de
+/// origem (`source`, só em arquivos JSON), usada no contexto dos erros de
+/// compilação, e cada
+/// framegroup o arquivo que o define (`source_file`), usado para resolver o
+/// seu `spritesheet`.
+///
+/// Os erros vêm em `AppearanceError::Context`; use `root()` para o erro original.
+pub fn parse_appearances<P: AsRef<Path>>(path: P) -> Result<AppearancesFile> {
+    parse(path.as_ref(), false)
+}
+
+/// Como `parse_appearances`, mas IDs repetidos não são erro
+///
+/// Para usar com `validate_appearances`, que reporta os IDs repetidos junto
+/// com os demais problemas em vez de parar no primeiro.
+pub fn parse_appearances_for_validation<P: AsRef<Path>>(path: P) -> Result<AppearancesFile> {
+    parse(path.as_ref(), true)
+}
+
+/// Mescla os includes, resolve os templates e deserializa o arquivo
+fn parse(path: &Path, keep_duplicate_ids: bool) -> Result<AppearancesFile> {
+    let (mut value, sources) = load_marked(path, keep_duplicate_ids)?;
+    resolve_templates(&mut value)?;
+    let framegroup_sources = take_framegroup_sources(&mut value);
+
+    let mut locations = locate_appearances(&sources);
+
+    let mut appearances: AppearancesFile = serde_path_to_error::deserialize(&value).map_err(|error| {
+        let context = path_context(&value, error.path(), path, &locations);
+        AppearanceError::Json(error.into_inner()).with_context(|current| *current = context)
+    })?;
+
+    for (appearance, framegroup_sources) in appearances.appearances.iter_mut().zip(framegroup_sources) {
+        appearance.source = locations
+            .get_mut(&(appearance.id as u64))
+            .and_then(VecDeque::pop_front);
+        for (framegroup, source_file) in appearance.framegroups.iter_mut().zip(framegroup_sources) {
+            framegroup.source_file = source_file;
+        }
+    }
+
+    Ok(appearances)
+}
+
+/// Remove as marcas de origem dos framegroups (`SOURCE_KEY`)
+///
+/// Retorna o arquivo de cada framegroup, por appearance e na ordem dos
+/// framegroups. As marcas que sobraram nos templates também são removidas.
+fn take_framegroup_sources(value: &mut Value) -> Vec<Vec<Option<PathBuf>>> {
+    let take = |item: &mut Value| -> Vec<Option<PathBuf>> {
+        let framegroups = item.get_mut("framegroups").and_then(Value::as_array_mut);
+        framegroups
+            .into_iter()
+            .flatten()
+            .map(|framegroup| {
+                let source = framegroup.as_object_mut()?.remove(SOURCE_KEY)?;
+                source.as_str().map(PathBuf::from)
+            })
+            .collect()
+    };
+
+    if let Some(templates) = value.get_mut("templates").and_then(Value::as_object_mut) {
+        templates.values_mut().for_each(|template| {
+            take(template);
+        });
+    }
+
+    match value.get_mut("appearances").and_then(Value::as_array_mut) {
+        Some(appearances) => appearances.iter_mut().map(take).collect(),
+        None => Vec::new(),
+    }
+}
+
+/// Encontra a posição de cada appearance nos arquivos JSON de origem
+///
+/// IDs repetidos têm uma posição por ocorrência, na ordem do arquivo. Nos
+/// outros formatos as appearances ficam sem posição.
+fn locate_appearances(sources: &HashMap<u64, PathBuf>) -> HashMap<u64, VecDeque<SourceLocation>> {
+    let mut files: HashMap<&PathBuf, Vec<u64>> = HashMap::new();
+    for (id, file) in sources {
+        files.entry(file).or_default().push(*id);
+    }
+
+    let mut locations: HashMap<u64, VecDeque<SourceLocation>> = HashMap::new();
+    for (file, ids) in files {
+        if !matches!(Format::from_path(file), Ok(Format::Json)) {
+            continue;
+        }
+        let Ok(text) = fs::read_to_string(file) else {
+            continue;
+        };
+        let (Some(spans), Ok(value)) = (json_spans(&text), serde_json::from_str::<Value>(&text)) else {
+            continue;
+        };
+
+        let appearances = value["appearances"].as_array().into_iter().flatten();
+        for (index, appearance) in appearances.enumerate() {
+            let Some(id) = appearance["id"].as_u64().filter(|id| ids.contains(id)) else {
+                continue;
+            };
+            let Some(&offset) = spans.get(&format!("appearances[{}]", index)) else {
+                continue;
+            };
+
+            let (line, column) = line_column(&text, offset);
+            locations.entry(id).or_default().push_back(SourceLocation {
+                file: file.clone(),
+                line,
+                column,
+            });
+        }
+    }
+
+    locations
+}
+
+/// Monta o contexto de um erro de deserialização a partir do caminho do campo
+fn path_context(
+    value: &Value, path: &serde_path_to_error::Path, root: &Path, locations: &HashMap<u64, VecDeque<SourceLocation>>,
+) -> ErrorContext {
+    let mut context = ErrorContext {
+        path: Some(path.to_string()),
+        ..Default::default()
+    };
+    let segments: Vec<&Segment> = path.iter().collect();
+
+    // appearances[i].framegroups[j].animations.<direção>.<campo>
+    let mut appearance = None;
+    if let [Segment::Map { key }, Segment::Seq { index }, rest @ ..] = segments.as_slice()
+        && key == "appearances"
+    {
+        let current = &value["appearances"][*index];
+        context.appearance_id = current["id"].as_u64().map(|id| id as u32);
+        context.appearance_name = current["name"].as_str().map(str::to_string);
+        appearance = Some(current);
+
+        if let [Segment::Map { key }, Segment::Seq { index }, rest @ ..] = rest
+            && key == "framegroups"
+        {
+            context.framegroup = current["framegroups"][*index]["name"].as_str().map(str::to_string);
+
+            if let [Segment::Map { key }, Segment::Map { key: direction }, ..] = rest
+                && key == "animations"
+            {
+                context.direction = Some(direction.clone());
+            }
+        }
+    }
+
+    context.field = match segments.last() {
+        Some(Segment::Map { key }) if Some(key) != context.direction.as_ref() => Some(key.clone()),
+        _ => None,
+    };
+
+    let start = appearance
+        .and_then(|appearance| appearance["id"].as_u64())
+        .and_then(|id| locations.get(&id)?.front());
+
+    match start {
+        Some(start) => context.locate_from(start.clone()),
+        // Fora de uma appearance localizada: procura o caminho no arquivo principal
+        None => context.location = locate_in_file(root, &path.to_string()),
+    }
+
+    context
+}
 
 /// Carrega e parseia o arquivo appearances.json
+///
+/// Equivalente a `parse_appearances`, mantido por compatibilidade.
 pub fn parse_appearances_json<P: AsRef<Path>>(path: P) -> Result<AppearancesFile> {
-    let contents = std::fs::read_to_string(path)?;
-    let appearances: AppearancesFile = serde_json::from_str(&contents)?;
-    Ok(appearances)
+    parse_appearances(path)
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::test_support::TempDir;
 
     #[test]
     fn test_parse_minimal() {
@@ -44,4 +224,202 @@ mod tests {
         assert_eq!(result.appearances[0].id, 1);
         assert_eq!(result.appearances[0].name, "warrior");
     }
+
+    #[test]
+    fn test_parse_non_square_footprint() {
+        let json = r#"{
+            "version": 2,
+            "appearances": [
+                { "id": 1, "name": "tree", "size": 32, "height": 64, "framegroups": [] },
+                {
+                    "id": 2,
+                    "name": "wagon",
+                    "width": 96,
+                    "height": 64,
+                    "footprint": { "width": 3, "height": 2 },
+                    "framegroups": []
+                }
+            ]
+        }"#;
+
+        let result: AppearancesFile = serde_json::from_str(json).unwrap();
+        assert_eq!(result.appearances[0].cell_size(), Some((32, 64)));
+        assert_eq!(result.appearances[0].footprint.width, 1);
+        assert_eq!(result.appearances[1].cell_size(), Some((96, 64)));
+        assert_eq!(result.appearances[1].footprint.height, 2);
+    }
+
+    #[test]
+    fn test_parse_frame_durations() {
+        let json = r#"{
+            "frame_count": 3,
+            "frame_durations": [400, { "min": 50, "max": 90 }, 60]
+        }"#;
+
+        let animation: crate::types::Animation = serde_json::from_str(json).unwrap();
+        let ranges: Vec<(u32, u32)> = animation.frame_durations.unwrap().iter().map(|d| d.range()).collect();
+        assert_eq!(ranges, vec![(400, 400), (50, 90), (60, 60)]);
+    }
+
+    #[test]
+    fn test_parse_playback_modes() {
+        use crate::types::{Animation, PlaybackMode, StartFrame};
+
+        let legacy: Animation = serde_json::from_str(r#"{ "frame_count": 2, "looped": false }"#).unwrap();
+        assert_eq!(legacy.playback_mode(), PlaybackMode::Once);
+
+        let door: Animation = serde_json::from_str(r#"{ "frame_count": 4, "playback": "once_and_hold" }"#).unwrap();
+        assert_eq!(door.playback_mode(), PlaybackMode::OnceAndHold);
+
+        let torch: Animation =
+            serde_json::from_str(r#"{ "frame_count": 4, "playback": "ping_pong", "start_frame": "random" }"#).unwrap();
+        assert_eq!(torch.playback_mode(), PlaybackMode::PingPong);
+        assert_eq!(torch.start_frame, StartFrame::Random);
+
+        let fixed: Animation = serde_json::from_str(r#"{ "frame_count": 4, "start_frame": { "frame": 2 } }"#).unwrap();
+        assert_eq!(fixed.start_frame, StartFrame::Frame(2));
+    }
+
+    #[test]
+    fn test_parse_attachments() {
+        use crate::types::{Animation, AttachmentPoint, Offset};
+
+        let json = r#"{
+            "frame_count": 2,
+            "attachments": {
+                "head": { "x": 32, "y": 8 },
+                "hand_r": [{ "x": 40, "y": 30 }, { "x": 44, "y": 28 }]
+            }
+        }"#;
+
+        let animation: Animation = serde_json::from_str(json).unwrap();
+        assert!(matches!(
+            animation.attachments["head"],
+            AttachmentPoint::Fixed(Offset { x: 32, y: 8 })
+        ));
+        assert!(matches!(&animation.attachments["hand_r"], AttachmentPoint::PerFrame(points) if points.len() == 2));
+    }
+
+    #[test]
+    fn test_serialize_direction_map() {
+        let json = r#"{
+            "version": 2,
+            "appearances": [{
+                "id": 1,
+                "name": "orc",
+                "size": 64,
+                "framegroups": [{
+                    "name": "walk",
+                    "spritesheet": "walk.png",
+                    "animations": { "null": { "frame_count": 1 }, "north": { "frame_count": 8, "duration": 100 } }
+                }]
+            }]
+        }"#;
+
+        let file: AppearancesFile = serde_json::from_str(json).unwrap();
+        let value = serde_json::to_value(&file).unwrap();
+        let animations = &value["appearances"][0]["framegroups"][0]["animations"];
+        assert_eq!(animations["null"]["frame_count"], 1);
+        assert_eq!(animations["north"]["duration"], 100);
+
+        let again: AppearancesFile = serde_json::from_value(value).unwrap();
+        assert_eq!(again.appearances[0].framegroups[0].animations.len(), 2);
+    }
+
+    #[test]
+    fn test_parse_error_carries_path_and_location() {
+        let root = TempDir::new("parser");
+        let path = root.write(
+            "appearances.json",
+            r#"{
+  "version": 2,
+  "appearances": [
+    { "id": 1, "name": "rock", "size": 32, "framegroups": [] },
+    {
+      "id": 2,
+      "name": "orc",
+      "size": 64,
+      "framegroups": [
+        {
+          "name": "walk",
+          "spritesheet": "walk.png",
+          "animations": {
+            "north": { "frame_count": "eight" }
+          }
+        }
+      ]
+    }
+  ]
+}"#,
+        );
+
+        let error = parse_appearances(&path).unwrap_err();
+        let context = error.context().unwrap();
+        assert!(matches!(error.root(), AppearanceError::Json(_)));
+        assert_eq!(context.appearance_id, Some(2));
+        assert_eq!(context.appearance_name.as_deref(), Some("orc"));
+        assert_eq!(context.framegroup.as_deref(), Some("walk"));
+        assert_eq!(context.direction.as_deref(), Some("north"));
+        assert_eq!(
+            context.path.as_deref(),
+            Some("appearances[1].framegroups[0].animations.north.frame_count")
+        );
+
+        let location = context.location.as_ref().unwrap();
+        assert_eq!((location.line, location.column), (14, 25));
+        assert!(location.snippet().unwrap().contains("14 |"));
+    }
+
+    #[test]
+    fn test_spritesheets_resolve_against_the_file_that_holds_them() {
+        let root = TempDir::new("parser-sources");
+        root.write("frag/walk.png", b"");
+        root.write("idle.png", b"");
+        root.write(
+            "frag/tpl.json",
+            r#"{
+                "templates": {
+                    "walker": {
+                        "size": 32,
+                        "framegroups": [
+                            { "name": "walk", "spritesheet": "walk.png", "animations": { "null": { "frame_count": 1 } } }
+                        ]
+                    }
+                }
+            }"#,
+        );
+        let path = root.write(
+            "main.json",
+            r#"{
+                "version": 2,
+                "include": ["frag/tpl.json"],
+                "appearances": [
+                    { "id": 1, "name": "orc", "extends": "walker" },
+                    {
+                        "id": 2,
+                        "name": "rock",
+                        "extends": "walker",
+                        "framegroups": [
+                            { "name": "idle", "spritesheet": "idle.png", "animations": { "null": { "frame_count": 1 } } }
+                        ]
+                    }
+                ]
+            }"#,
+        );
+
+        let file = parse_appearances(&path).unwrap();
+        let resolver = crate::PathResolver::new(root.join("elsewhere"));
+        let resolve = |index: usize| {
+            let appearance = &file.appearances[index];
+            let framegroup = &appearance.framegroups[0];
+            resolver
+                .resolve_for(appearance, framegroup, framegroup.spritesheet.as_deref().unwrap())
+                .unwrap()
+        };
+
+        // Do template (em frag/) e da própria appearance (em main.json)
+        assert_eq!(resolve(0), root.join("frag/walk.png"));
+        assert_eq!(resolve(1), root.join("idle.png"));
+        assert!(!file.templates["walker"].to_string().contains(SOURCE_KEY));
+    }
 }
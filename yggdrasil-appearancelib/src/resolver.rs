@@ -0,0 +1,245 @@
+use crate::error::{AppearanceError, Result};
+use crate::types::{Appearance, FrameGroup};
+use std::env;
+use std::path::{Path, PathBuf};
+
+/// Resolve os caminhos de spritesheets
+///
+/// Antes da busca, `~` no início e variáveis de ambiente (`$NOME` ou
+/// `${NOME}`, com `NOME` começando por letra ou `_`) são expandidos; `$$`
+/// vira um `$` literal e um `$` que não inicia um nome (ex.: `fx$1.png`) é
+/// mantido. Caminhos absolutos são usados como estão; os
+/// relativos são procurados, nesta ordem:
+///
+/// 1. na pasta do arquivo que contém o caminho: o que define o framegroup
+///    (`FrameGroup::source_file`, que pode ser um fragmento ou o arquivo de um
+///    template) ou, se desconhecido, o da appearance (`Appearance::source`)
+/// 2. em `base_path` (a raiz do projeto)
+/// 3. em cada raiz de busca adicional, na ordem em que foram informadas
+///
+/// O primeiro arquivo existente vence. Se nenhum existir, o erro lista todos
+/// os locais tentados.
+#[derive(Debug, Clone)]
+pub struct PathResolver {
+    base_path: PathBuf,
+    roots:     Vec<PathBuf>,
+}
+
+impl PathResolver {
+    /// Resolver sem raízes adicionais
+    pub fn new<P: AsRef<Path>>(base_path: P) -> Self {
+        PathResolver {
+            base_path: base_path.as_ref().to_path_buf(),
+            roots:     Vec::new(),
+        }
+    }
+
+    /// Acrescenta raízes de busca, consultadas depois de `base_path`
+    ///
+    /// As raízes também passam pela expansão de `~` e variáveis de ambiente.
+    pub fn with_roots<I, P>(mut self, roots: I) -> Result<Self>
+    where
+        I: IntoIterator<Item = P>,
+        P: AsRef<Path>,
+    {
+        for root in roots {
+            let root = root.as_ref().to_string_lossy();
+            self.roots.push(PathBuf::from(expand(&root)?));
+        }
+        Ok(self)
+    }
+
+    pub fn base_path(&self) -> &Path {
+        &self.base_path
+    }
+
+    pub fn roots(&self) -> &[PathBuf] {
+        &self.roots
+    }
+
+    /// Locais onde `path` é procurado, em ordem e sem repetições
+    ///
+    /// `source` é o arquivo que contém o caminho (None se desconhecido).
+    pub fn candidates(&self, path: &str, source: Option<&Path>) -> Result<Vec<PathBuf>> {
+        let expanded = PathBuf::from(expand(path)?);
+        if expanded.is_absolute() {
+            return Ok(vec![expanded]);
+        }
+
+        // Arquivo na pasta atual (`appearances.json`) tem pai vazio
+        let source_dir = source.map(|file| {
+            file.parent()
+                .filter(|dir| !dir.as_os_str().is_empty())
+                .unwrap_or(Path::new("."))
+        });
+
+        let mut candidates: Vec<PathBuf> = Vec::new();
+        for dir in source_dir
+            .into_iter()
+            .chain([self.base_path.as_path()])
+            .chain(self.roots.iter().map(PathBuf::as_path))
+        {
+            let candidate = dir.join(&expanded);
+            if !candidates.contains(&candidate) {
+                candidates.push(candidate);
+            }
+        }
+        Ok(candidates)
+    }
+
+    /// Resolve `path`, retornando o primeiro local em que o arquivo existe
+    pub fn resolve(&self, path: &str, source: Option<&Path>) -> Result<PathBuf> {
+        let tried = self.candidates(path, source)?;
+
+        match tried.iter().find(|candidate| candidate.is_file()) {
+            Some(found) => Ok(found.clone()),
+            None => Err(AppearanceError::SpritesheetNotFound {
+                path: path.to_string(),
+                tried,
+            }),
+        }
+    }
+
+    /// Resolve um spritesheet relativo ao arquivo que define o framegroup
+    pub fn resolve_for(&self, appearance: &Appearance, framegroup: &FrameGroup, path: &str) -> Result<PathBuf> {
+        let source = framegroup
+            .source_file
+            .as_deref()
+            .or_else(|| appearance.source.as_ref().map(|source| source.file.as_path()));
+        self.resolve(path, source)
+    }
+}
+
+/// Expande `~` no início, variáveis de ambiente (`$NOME` e `${NOME}`) e `$$`
+fn expand(path: &str) -> Result<String> {
+    expand_with(path, |name| env::var(name).ok())
+}
+
+/// Como `expand`, lendo as variáveis com `lookup` em vez do ambiente do processo
+fn expand_with(path: &str, lookup: impl Fn(&str) -> Option<String>) -> Result<String> {
+    let variable = |name: &str| {
+        lookup(name).ok_or_else(|| {
+            AppearanceError::InvalidData(format!(
+                "Environment variable `{}` used in path '{}' is not set",
+                name, path
+            ))
+        })
+    };
+
+    let mut expanded = String::with_capacity(path.len());
+    let mut rest = path;
+
+    if rest == "~" || rest.starts_with("~/") || rest.starts_with("~\\") {
+        expanded.push_str(&variable("HOME").or_else(|_| variable("USERPROFILE"))?);
+        rest = &rest[1..];
+    }
+
+    while let Some(dollar) = rest.find('$') {
+        expanded.push_str(&rest[..dollar]);
+        rest = &rest[dollar + 1..];
+
+        // `$$` é um `$` literal
+        if let Some(after) = rest.strip_prefix('$') {
+            expanded.push('$');
+            rest = after;
+            continue;
+        }
+
+        let (name, after) = match rest.strip_prefix('{') {
+            Some(braced) => {
+                let end = braced
+                    .find('}')
+                    .ok_or_else(|| AppearanceError::InvalidData(format!("Unclosed `${{` in path '{}'", path)))?;
+                (&braced[..end], &braced[end + 1..])
+            }
+            // Nomes sem chaves começam por letra ou `_` (`fx$1.png` não é uma variável)
+            None if rest.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_') => {
+                let end = rest
+                    .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+                    .unwrap_or(rest.len());
+                (&rest[..end], &rest[end..])
+            }
+            None => ("", rest),
+        };
+
+        // `$` sem nome é mantido como está
+        if name.is_empty() {
+            expanded.push('$');
+            continue;
+        }
+
+        expanded.push_str(&variable(name)?);
+        rest = after;
+    }
+
+    expanded.push_str(rest);
+    Ok(expanded)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::TempDir;
+
+    #[test]
+    fn test_resolves_relative_to_source_then_roots() {
+        let root = TempDir::new("resolver");
+        let creatures = root.join("creatures");
+        let shared = root.join("shared");
+        root.write("creatures/walk.png", b"");
+        root.write("shared/fire.png", b"");
+
+        let resolver = PathResolver::new(root.path()).with_roots([&shared]).unwrap();
+        let source = creatures.join("orc.json");
+
+        // Relativo ao arquivo de origem
+        assert_eq!(
+            resolver.resolve("walk.png", Some(&source)).unwrap(),
+            creatures.join("walk.png")
+        );
+        // Relativo à raiz do projeto e às raízes adicionais
+        assert_eq!(
+            resolver.resolve("creatures/walk.png", None).unwrap(),
+            root.join("creatures/walk.png")
+        );
+        assert_eq!(
+            resolver.resolve("fire.png", Some(&source)).unwrap(),
+            shared.join("fire.png")
+        );
+
+        // O erro lista todos os locais tentados
+        match resolver.resolve("missing.png", Some(&source)) {
+            Err(AppearanceError::SpritesheetNotFound { tried, .. }) => assert_eq!(
+                tried,
+                [
+                    creatures.join("missing.png"),
+                    root.join("missing.png"),
+                    shared.join("missing.png")
+                ]
+            ),
+            other => panic!("unexpected result: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_expands_home_and_variables() {
+        let lookup = |name: &str| match name {
+            "HOME" => Some("/home/dev".to_string()),
+            "ART" => Some("/art".to_string()),
+            _ => None,
+        };
+
+        assert_eq!(
+            expand_with("~/sprites/orc.png", lookup).unwrap(),
+            "/home/dev/sprites/orc.png"
+        );
+        assert_eq!(expand_with("$ART/fire.png", lookup).unwrap(), "/art/fire.png");
+        assert_eq!(expand_with("${ART}_old/fire.png", lookup).unwrap(), "/art_old/fire.png");
+        assert!(expand_with("$MISSING/fire.png", lookup).is_err());
+
+        // `$` que não inicia um nome e o escape `$$`
+        assert_eq!(expand_with("fx$1.png", lookup).unwrap(), "fx$1.png");
+        assert_eq!(expand_with("price$.png", lookup).unwrap(), "price$.png");
+        assert_eq!(expand_with("$$ART/fire.png", lookup).unwrap(), "$ART/fire.png");
+    }
+}
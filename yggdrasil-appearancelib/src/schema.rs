@@ -0,0 +1,85 @@
+use crate::error::Result;
+use crate::types::AppearancesFile;
+use schemars::Schema;
+use schemars::generate::SchemaSettings;
+use std::fs;
+use std::path::Path;
+
+/// Gera o JSON Schema do appearances.json
+///
+/// Usa o draft-07, que é o suportado por completo pelo VS Code e pela maioria
+/// dos editores. As descrições vêm dos doc comments dos tipos.
+pub fn appearances_schema() -> Schema {
+    let mut schema = SchemaSettings::draft07()
+        .into_generator()
+        .into_root_schema_for::<AppearancesFile>();
+
+    // Fragmentos de `include` usam o mesmo schema e não têm `version`
+    schema.remove("required");
+    schema
+}
+
+/// Escreve o JSON Schema do appearances.json em um arquivo
+pub fn write_schema(path: &Path) -> Result<()> {
+    let contents = serde_json::to_string_pretty(&appearances_schema())?;
+    fs::write(path, contents)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::FrameGroup;
+    use serde_json::{Value, json};
+
+    #[test]
+    fn test_schema_describes_direction_keys_and_defaults() {
+        let schema = appearances_schema();
+
+        let animation = schema.pointer("/definitions/Animation/properties").unwrap();
+        assert_eq!(animation["frame_count"]["default"], 1);
+        assert_eq!(animation["looped"]["default"], true);
+
+        let directions = schema
+            .pointer("/definitions/FrameGroup/properties/animations/properties")
+            .and_then(|keys| keys.as_object())
+            .unwrap();
+        assert!(directions.contains_key("null"));
+        assert!(directions.contains_key("northwest"));
+
+        // Toda chave do schema precisa ser aceita pelo parser
+        for key in directions.keys() {
+            let framegroup = json!({
+                "name": "idle",
+                "spritesheet": "idle.png",
+                "animations": { key: { "frame_count": 1 } }
+            });
+            serde_json::from_value::<FrameGroup>(framegroup).unwrap_or_else(|e| panic!("{}: {}", key, e));
+        }
+
+        let orientations: Vec<&Value> = schema
+            .pointer("/definitions/Orientation/oneOf")
+            .and_then(|variants| variants.as_array())
+            .unwrap()
+            .iter()
+            .map(|variant| &variant["const"])
+            .collect();
+        assert_eq!(orientations, [&json!("vertical"), &json!("horizontal")]);
+    }
+
+    #[test]
+    fn test_extends_relaxes_required_fields() {
+        let schema = appearances_schema();
+        let appearance = schema.pointer("/definitions/Appearance").unwrap();
+
+        assert!(appearance.get("required").is_none());
+        assert!(schema.get("required").is_none());
+        assert_eq!(appearance["then"]["required"], json!(["id", "name"]));
+        assert!(
+            appearance["else"]["required"]
+                .as_array()
+                .unwrap()
+                .contains(&json!("framegroups"))
+        );
+    }
+}
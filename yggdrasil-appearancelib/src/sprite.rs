@@ -1,18 +1,41 @@
 use crate::error::{AppearanceError, Result};
-use crate::types::{Orientation, SpriteData};
+use crate::types::{Color, Direction, Orientation, RotationMode, SpriteData};
 use flate2::Compression;
 use flate2::write::GzEncoder;
-use image::{DynamicImage, GenericImageView};
+use image::{DynamicImage, GenericImageView, RgbaImage};
 use std::io::Write;
 use std::path::Path;
 
 const COMPRESSION_LEVEL: u32 = 6;
 
+/// Direções em sentido horário a partir de North, em passos de 45°
+const CLOCKWISE_DIRECTIONS: [Direction; 8] = [
+    Direction::North,
+    Direction::NorthEast,
+    Direction::East,
+    Direction::SouthEast,
+    Direction::South,
+    Direction::SouthWest,
+    Direction::West,
+    Direction::NorthWest,
+];
+
+/// Sprite compactada com os pixels RGBA descompactados
+///
+/// Os pixels só são usados durante a compilação (máscaras de alpha e cor do
+/// minimapa) e não fazem parte de `SpriteData` nem do .spr.
+#[derive(Debug, Clone)]
+pub(crate) struct RgbaSprite {
+    pub data:   SpriteData,
+    pub pixels: Vec<u8>,
+}
+
 /// Recorta um spritesheet em sprites individuais
 ///
 /// # Parâmetros
 /// - `spritesheet_path`: Caminho para o spritesheet
-/// - `sprite_size`: Tamanho de cada sprite (largura e altura)
+/// - `sprite_width`: Largura de cada sprite (célula)
+/// - `sprite_height`: Altura de cada sprite (célula)
 /// - `num_frames`: Número de frames
 /// - `num_directions`: Número de direções, 0 se não houver direções
 /// - `orientation`: Orientação do spritesheet (Vertical ou Horizontal)
@@ -21,54 +44,228 @@ const COMPRESSION_LEVEL: u32 = 6;
 /// Um vetor de `SpriteData`, onde cada elemento é uma sprite individual
 /// recortada
 pub fn slice_spritesheet<P: AsRef<Path>>(
-    spritesheet_path: P, sprite_size: u32, num_frames: u32, num_directions: u32, orientation: Orientation,
+    spritesheet_path: P, sprite_width: u32, sprite_height: u32, num_frames: u32, num_directions: u32,
+    orientation: Orientation,
 ) -> Result<Vec<SpriteData>> {
-    let path_ref = spritesheet_path.as_ref();
+    let sprites = slice_spritesheet_rgba(
+        spritesheet_path,
+        sprite_width,
+        sprite_height,
+        num_frames,
+        num_directions,
+        orientation,
+    )?;
 
-    // Verifica se o arquivo existe
-    if !path_ref.exists() {
-        return Err(AppearanceError::SpriteNotFound {
-            path: path_ref.display().to_string(),
-        });
+    Ok(sprites.into_iter().map(|sprite| sprite.data).collect())
+}
+
+/// Como `slice_spritesheet`, mantendo os pixels RGBA de cada sprite
+pub(crate) fn slice_spritesheet_rgba<P: AsRef<Path>>(
+    spritesheet_path: P, sprite_width: u32, sprite_height: u32, num_frames: u32, num_directions: u32,
+    orientation: Orientation,
+) -> Result<Vec<RgbaSprite>> {
+    slice_spritesheet_images(
+        spritesheet_path,
+        sprite_width,
+        sprite_height,
+        num_frames,
+        num_directions,
+        orientation,
+    )?
+    .iter()
+    .map(image_to_sprite_data)
+    .collect()
+}
+
+/// Gera as 8 direções de uma animação a partir de um spritesheet fonte
+///
+/// - `RotationMode::Step45`: o spritesheet tem uma única linha (North), que é
+///   girada em passos de 45° com amostragem nearest-neighbour
+/// - `RotationMode::Orthogonal`: o spritesheet tem duas linhas (North e
+///   NorthEast), giradas em passos exatos de 90°
+///
+/// Ambos os modos exigem células quadradas. Os 45° giram dentro da própria
+/// célula, então cantos que saem do círculo inscrito são cortados; formas de
+/// colisão e anchors não são girados (a validação os rejeita nesse modo).
+///
+/// # Retorna
+/// As sprites de cada direção, em sentido horário a partir de North
+pub fn generate_rotated_directions<P: AsRef<Path>>(
+    spritesheet_path: P, sprite_width: u32, sprite_height: u32, num_frames: u32, orientation: Orientation,
+    mode: RotationMode,
+) -> Result<Vec<(Direction, Vec<SpriteData>)>> {
+    let directions = generate_rotated_directions_rgba(
+        spritesheet_path,
+        sprite_width,
+        sprite_height,
+        num_frames,
+        orientation,
+        mode,
+    )?;
+
+    Ok(directions
+        .into_iter()
+        .map(|(direction, sprites)| (direction, sprites.into_iter().map(|sprite| sprite.data).collect()))
+        .collect())
+}
+
+/// Como `generate_rotated_directions`, mantendo os pixels RGBA de cada sprite
+pub(crate) fn generate_rotated_directions_rgba<P: AsRef<Path>>(
+    spritesheet_path: P, sprite_width: u32, sprite_height: u32, num_frames: u32, orientation: Orientation,
+    mode: RotationMode,
+) -> Result<Vec<(Direction, Vec<RgbaSprite>)>> {
+    // Rotações de 90° trocariam largura e altura da célula
+    if sprite_width != sprite_height {
+        return Err(AppearanceError::InvalidData(format!(
+            "Rotation requires square cells, got {}x{} for spritesheet '{}'",
+            sprite_width,
+            sprite_height,
+            spritesheet_path.as_ref().display()
+        )));
     }
 
-    // Carrega a imagem
-    let spritesheet = image::open(path_ref)?;
-    let (sheet_width, sheet_height) = spritesheet.dimensions();
+    let source_rows = match mode {
+        RotationMode::Step45 => 0,
+        RotationMode::Orthogonal => 2,
+    };
+    let frames = slice_spritesheet_images(
+        spritesheet_path,
+        sprite_width,
+        sprite_height,
+        num_frames,
+        source_rows,
+        orientation,
+    )?;
 
-    // Calcula dimensões esperadas baseado na orientação
-    let (expected_width, expected_height) = match orientation {
+    // Separa os frames de cada linha fonte (North e, se houver, NorthEast)
+    let row_frames = |row: usize| -> Vec<&DynamicImage> {
+        (0..num_frames as usize)
+            .map(|frame| match orientation {
+                Orientation::Horizontal => &frames[frame * source_rows.max(1) as usize + row],
+                Orientation::Vertical => &frames[row * num_frames as usize + frame],
+            })
+            .collect()
+    };
+
+    CLOCKWISE_DIRECTIONS
+        .iter()
+        .enumerate()
+        .map(|(step, &direction)| {
+            let (source, degrees) = match mode {
+                RotationMode::Step45 => (row_frames(0), step as u32 * 45),
+                RotationMode::Orthogonal => (row_frames(step % 2), (step as u32 / 2) * 90),
+            };
+
+            let sprites = source
+                .into_iter()
+                .map(|image| image_to_sprite_data(&rotate_nearest(image, degrees)))
+                .collect::<Result<Vec<_>>>()?;
+
+            Ok((direction, sprites))
+        })
+        .collect()
+}
+
+/// Gira uma imagem em sentido horário, mantendo as dimensões originais
+///
+/// Múltiplos de 90° usam rotações exatas; os demais ângulos usam amostragem
+/// nearest-neighbour (sem interpolação, preservando pixel art). Pixels que
+/// caem fora da imagem original ficam transparentes, e os que giram para fora
+/// dela (os cantos, em ângulos diagonais) são perdidos.
+fn rotate_nearest(image: &DynamicImage, degrees: u32) -> DynamicImage {
+    match degrees % 360 {
+        0 => return image.clone(),
+        90 => return image.rotate90(),
+        180 => return image.rotate180(),
+        270 => return image.rotate270(),
+        _ => {}
+    }
+
+    let source = image.to_rgba8();
+    let (width, height) = source.dimensions();
+    let mut rotated = RgbaImage::new(width, height);
+
+    let (sin, cos) = (degrees as f32).to_radians().sin_cos();
+    let center_x = width as f32 / 2.0;
+    let center_y = height as f32 / 2.0;
+
+    for y in 0..height {
+        for x in 0..width {
+            // Rotação inversa a partir do centro do pixel de destino
+            let dx = x as f32 + 0.5 - center_x;
+            let dy = y as f32 + 0.5 - center_y;
+            let source_x = (dx * cos + dy * sin + center_x).floor();
+            let source_y = (-dx * sin + dy * cos + center_y).floor();
+
+            if source_x >= 0.0 && source_y >= 0.0 && source_x < width as f32 && source_y < height as f32 {
+                rotated.put_pixel(x, y, *source.get_pixel(source_x as u32, source_y as u32));
+            }
+        }
+    }
+
+    DynamicImage::ImageRgba8(rotated)
+}
+
+/// Dimensões (largura, altura) esperadas de um spritesheet
+///
+/// `num_directions` é 0 quando a animação não tem direções (uma única linha ou coluna).
+pub fn expected_sheet_size(
+    sprite_width: u32, sprite_height: u32, num_frames: u32, num_directions: u32, orientation: Orientation,
+) -> (u32, u32) {
+    match orientation {
         Orientation::Horizontal => {
             // Horizontal: direções em colunas (lado a lado), frames em linhas (empilhados)
             // Exemplo: [N][E][S][W] ← Frame 1
             //          [N][E][S][W] ← Frame 2
             let width = if num_directions > 0 {
-                sprite_size * num_directions
+                sprite_width * num_directions
             } else {
-                sprite_size
+                sprite_width
             };
-            let height = sprite_size * num_frames;
+            let height = sprite_height * num_frames;
             (width, height)
         }
         Orientation::Vertical => {
             // Vertical: frames em colunas (lado a lado), direções em linhas (empilhadas)
             // Exemplo: [N1][N2][N3]... ← Norte
             //          [E1][E2][E3]... ← Leste
-            let width = sprite_size * num_frames;
+            let width = sprite_width * num_frames;
             let height = if num_directions > 0 {
-                sprite_size * num_directions
+                sprite_height * num_directions
             } else {
-                sprite_size
+                sprite_height
             };
             (width, height)
         }
-    };
+    }
+}
+
+/// Recorta um spritesheet em imagens individuais (sem compactar)
+fn slice_spritesheet_images<P: AsRef<Path>>(
+    spritesheet_path: P, sprite_width: u32, sprite_height: u32, num_frames: u32, num_directions: u32,
+    orientation: Orientation,
+) -> Result<Vec<DynamicImage>> {
+    let path_ref = spritesheet_path.as_ref();
+
+    // Verifica se o arquivo existe
+    if !path_ref.exists() {
+        return Err(AppearanceError::SpriteNotFound {
+            path: path_ref.display().to_string(),
+        });
+    }
+
+    // Carrega a imagem
+    let spritesheet = image::open(path_ref)?;
+    let (sheet_width, sheet_height) = spritesheet.dimensions();
+
+    // Calcula dimensões esperadas baseado na orientação
+    let (expected_width, expected_height) =
+        expected_sheet_size(sprite_width, sprite_height, num_frames, num_directions, orientation);
 
     // Valida dimensões do spritesheet
     if sheet_width != expected_width || sheet_height != expected_height {
         return Err(AppearanceError::InvalidDimensions {
-            name: path_ref.display().to_string(),
-            animation: "spritesheet".to_string(),
+            path: path_ref.display().to_string(),
             expected_width,
             expected_height,
             actual_width: sheet_width,
@@ -86,12 +283,10 @@ pub fn slice_spritesheet<P: AsRef<Path>>(
             for row in 0..num_frames {
                 let cols = if num_directions > 0 { num_directions } else { 1 };
                 for col in 0..cols {
-                    let x = col * sprite_size;
-                    let y = row * sprite_size;
+                    let x = col * sprite_width;
+                    let y = row * sprite_height;
 
-                    let sprite_img = spritesheet.crop_imm(x, y, sprite_size, sprite_size);
-                    let sprite_data = image_to_sprite_data(&sprite_img)?;
-                    sprites.push(sprite_data);
+                    sprites.push(spritesheet.crop_imm(x, y, sprite_width, sprite_height));
                 }
             }
         }
@@ -101,12 +296,10 @@ pub fn slice_spritesheet<P: AsRef<Path>>(
             let rows = if num_directions > 0 { num_directions } else { 1 };
             for row in 0..rows {
                 for col in 0..num_frames {
-                    let x = col * sprite_size;
-                    let y = row * sprite_size;
+                    let x = col * sprite_width;
+                    let y = row * sprite_height;
 
-                    let sprite_img = spritesheet.crop_imm(x, y, sprite_size, sprite_size);
-                    let sprite_data = image_to_sprite_data(&sprite_img)?;
-                    sprites.push(sprite_data);
+                    sprites.push(spritesheet.crop_imm(x, y, sprite_width, sprite_height));
                 }
             }
         }
@@ -115,8 +308,8 @@ pub fn slice_spritesheet<P: AsRef<Path>>(
     Ok(sprites)
 }
 
-/// Converte uma DynamicImage em SpriteData compactado
-fn image_to_sprite_data(image: &DynamicImage) -> Result<SpriteData> {
+/// Converte uma DynamicImage em SpriteData compactado (com os pixels RGBA)
+fn image_to_sprite_data(image: &DynamicImage) -> Result<RgbaSprite> {
     let (width, height) = image.dimensions();
 
     // Extrai pixels RGBA
@@ -126,10 +319,13 @@ fn image_to_sprite_data(image: &DynamicImage) -> Result<SpriteData> {
     // Compacta os pixels
     let compressed_pixels = compress_pixels(&pixels)?;
 
-    Ok(SpriteData {
-        width,
-        height,
-        compressed_pixels,
+    Ok(RgbaSprite {
+        data: SpriteData {
+            width,
+            height,
+            compressed_pixels,
+        },
+        pixels,
     })
 }
 
@@ -141,3 +337,145 @@ fn compress_pixels(pixels: &[u8]) -> Result<Vec<u8>> {
     encoder.finish()?;
     Ok(compressed)
 }
+
+/// Calcula a cor média dos pixels opacos (alpha > `alpha_threshold`)
+///
+/// Retorna `None` se a sprite for totalmente transparente.
+pub fn average_color(pixels: &[u8], alpha_threshold: u8) -> Option<Color> {
+    let mut sum = [0u64; 3];
+    let mut count = 0u64;
+
+    for pixel in pixels.chunks_exact(4).filter(|pixel| pixel[3] > alpha_threshold) {
+        for (channel, value) in sum.iter_mut().zip(pixel) {
+            *channel += *value as u64;
+        }
+        count += 1;
+    }
+
+    (count > 0).then(|| Color {
+        r: (sum[0] / count) as u8,
+        g: (sum[1] / count) as u8,
+        b: (sum[2] / count) as u8,
+    })
+}
+
+/// Calcula a cor dominante dos pixels opacos (alpha > `alpha_threshold`)
+///
+/// As cores são agrupadas com 4 bits por canal; retorna a média do grupo mais
+/// frequente. Retorna `None` se a sprite for totalmente transparente.
+pub fn dominant_color(pixels: &[u8], alpha_threshold: u8) -> Option<Color> {
+    let bucket =
+        |pixel: &[u8]| ((pixel[0] as usize >> 4) << 8) | ((pixel[1] as usize >> 4) << 4) | (pixel[2] as usize >> 4);
+    let opaque = || pixels.chunks_exact(4).filter(|pixel| pixel[3] > alpha_threshold);
+
+    let mut counts = vec![0u32; 4096];
+    for pixel in opaque() {
+        counts[bucket(pixel)] += 1;
+    }
+
+    // Em caso de empate vence o grupo de menor índice (resultado determinístico)
+    let (dominant, &count) = counts.iter().enumerate().rev().max_by_key(|(_, count)| **count)?;
+    if count == 0 {
+        return None;
+    }
+
+    let members: Vec<u8> = opaque()
+        .filter(|pixel| bucket(pixel) == dominant)
+        .flatten()
+        .copied()
+        .collect();
+    average_color(&members, alpha_threshold)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::Rgba;
+
+    /// Imagem 4x4 transparente com um único pixel opaco no topo
+    fn arrow_up() -> DynamicImage {
+        let mut image = RgbaImage::new(4, 4);
+        image.put_pixel(1, 0, Rgba([255, 0, 0, 255]));
+        DynamicImage::ImageRgba8(image)
+    }
+
+    #[test]
+    fn test_rotate_exact_quarter_turns() {
+        let rotated = rotate_nearest(&arrow_up(), 90).to_rgba8();
+        assert_eq!(rotated.get_pixel(3, 1)[3], 255);
+
+        let rotated = rotate_nearest(&arrow_up(), 180).to_rgba8();
+        assert_eq!(rotated.get_pixel(2, 3)[3], 255);
+    }
+
+    #[test]
+    fn test_rotate_nearest_keeps_size_and_palette() {
+        let rotated = rotate_nearest(&arrow_up(), 45).to_rgba8();
+        assert_eq!(rotated.dimensions(), (4, 4));
+        assert!(rotated.pixels().all(|p| p[3] == 0 || *p == Rgba([255, 0, 0, 255])));
+    }
+
+    #[test]
+    fn test_orthogonal_rotation_maps_rows_to_directions() {
+        let root = crate::test_support::TempDir::new("rotation-rows");
+        let colors = [[255, 0, 0], [0, 0, 255]];
+
+        for orientation in [Orientation::Vertical, Orientation::Horizontal] {
+            // Células 4x4, 2 frames (vermelho, azul): North com um pixel no
+            // topo e NorthEast com um pixel no canto superior direito
+            let mut sheet = RgbaImage::new(8, 8);
+            for (frame, [r, g, b]) in colors.into_iter().enumerate() {
+                for (row, x) in [(0, 1), (1, 3)] {
+                    let (column, line) = match orientation {
+                        Orientation::Vertical => (frame, row),
+                        Orientation::Horizontal => (row, frame),
+                    };
+                    sheet.put_pixel(column as u32 * 4 + x, line as u32 * 4, Rgba([r, g, b, 255]));
+                }
+            }
+            let path = root.join(format!("{:?}.png", orientation));
+            sheet.save(&path).unwrap();
+
+            let directions = generate_rotated_directions_rgba(&path, 4, 4, 2, orientation, RotationMode::Orthogonal)
+                .unwrap();
+
+            // (x, y) do pixel opaco de cada frame; E e SE são N e NE girados 90°
+            for (direction, expected) in [
+                (Direction::North, (1, 0)),
+                (Direction::NorthEast, (3, 0)),
+                (Direction::East, (3, 1)),
+                (Direction::SouthEast, (3, 3)),
+            ] {
+                let (_, sprites) = directions.iter().find(|(d, _)| *d == direction).unwrap();
+                for (sprite, color) in sprites.iter().zip(colors) {
+                    let opaque: Vec<((u32, u32), &[u8])> = sprite
+                        .pixels
+                        .chunks_exact(4)
+                        .enumerate()
+                        .filter(|(_, pixel)| pixel[3] > 0)
+                        .map(|(index, pixel)| ((index as u32 % 4, index as u32 / 4), &pixel[..3]))
+                        .collect();
+                    assert_eq!(opaque, [(expected, &color[..])], "{:?} {:?}", orientation, direction);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_minimap_colors() {
+        // 3 pixels verdes, 2 vermelhos e 1 transparente (ignorado)
+        let mut pixels = Vec::new();
+        for _ in 0..3 {
+            pixels.extend_from_slice(&[0, 200, 0, 255]);
+        }
+        for _ in 0..2 {
+            pixels.extend_from_slice(&[250, 0, 0, 255]);
+        }
+        pixels.extend_from_slice(&[0, 0, 255, 0]);
+
+        assert_eq!(average_color(&pixels, 0), Some(Color { r: 100, g: 120, b: 0 }));
+        assert_eq!(dominant_color(&pixels, 0), Some(Color { r: 0, g: 200, b: 0 }));
+        assert_eq!(average_color(&[0, 0, 0, 0], 0), None);
+        assert_eq!(dominant_color(&[0, 0, 0, 0], 0), None);
+    }
+}
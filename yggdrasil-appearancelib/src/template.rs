@@ -0,0 +1,182 @@
+use crate::error::{AppearanceError, Result};
+use serde_json::{Map, Value};
+
+/// Resolve os templates do appearances.json
+///
+/// Cada appearance com `"extends": "<template>"` recebe os campos do template
+/// (que pode estender outro template). Objetos são mesclados recursivamente,
+/// com prioridade para a appearance; arrays e demais valores são substituídos.
+/// Nas strings do template, `{name}` e `{id}` são trocados pelo nome e ID da
+/// appearance (ex.: `"{name}/walk.png"`).
+pub fn resolve_templates(file: &mut Value) -> Result<()> {
+    let templates = match file.get("templates") {
+        Some(Value::Object(templates)) => templates.clone(),
+        Some(_) => {
+            return Err(AppearanceError::InvalidData(
+                "`templates` must be an object".to_string(),
+            ));
+        }
+        None => Map::new(),
+    };
+
+    let Some(Value::Array(appearances)) = file.get_mut("appearances") else {
+        return Ok(());
+    };
+
+    for appearance in appearances {
+        let Some(parent) = appearance.get("extends") else {
+            continue;
+        };
+
+        let name = appearance
+            .get("name")
+            .and_then(Value::as_str)
+            .unwrap_or_default()
+            .to_string();
+        let parent = parent.as_str().ok_or_else(|| {
+            AppearanceError::InvalidData(format!("Appearance '{}': `extends` must be a string", name))
+        })?;
+
+        let mut resolved = flatten_template(&templates, parent, &format!("Appearance '{}'", name), &mut Vec::new())?;
+
+        let id = appearance.get("id").map(Value::to_string).unwrap_or_default();
+        substitute(&mut resolved, &name, &id);
+
+        merge(&mut resolved, appearance.take());
+        *appearance = resolved;
+    }
+
+    Ok(())
+}
+
+/// Retorna o template com toda a cadeia de `extends` já mesclada
+fn flatten_template(
+    templates: &Map<String, Value>, name: &str, referrer: &str, stack: &mut Vec<String>,
+) -> Result<Value> {
+    if stack.iter().any(|entry| entry == name) {
+        stack.push(name.to_string());
+        return Err(AppearanceError::InvalidData(format!(
+            "Template cycle: {}",
+            stack.join(" -> ")
+        )));
+    }
+
+    let template = templates
+        .get(name)
+        .ok_or_else(|| AppearanceError::InvalidData(format!("{} extends unknown template '{}'", referrer, name)))?;
+
+    let Some(parent) = template.get("extends") else {
+        return Ok(template.clone());
+    };
+
+    let parent = parent
+        .as_str()
+        .ok_or_else(|| AppearanceError::InvalidData(format!("Template '{}': `extends` must be a string", name)))?;
+
+    stack.push(name.to_string());
+    let mut resolved = flatten_template(templates, parent, &format!("Template '{}'", name), stack)?;
+    stack.pop();
+
+    merge(&mut resolved, template.clone());
+    Ok(resolved)
+}
+
+/// Mescla `overrides` sobre `base` (objetos recursivamente, o resto substitui)
+fn merge(base: &mut Value, overrides: Value) {
+    match (base, overrides) {
+        (Value::Object(base), Value::Object(overrides)) => {
+            for (key, value) in overrides {
+                match base.get_mut(&key) {
+                    Some(existing) => merge(existing, value),
+                    None => {
+                        base.insert(key, value);
+                    }
+                }
+            }
+        }
+        (base, overrides) => *base = overrides,
+    }
+}
+
+/// Troca `{name}` e `{id}` em todas as strings
+fn substitute(value: &mut Value, name: &str, id: &str) {
+    match value {
+        Value::String(text) if text.contains('{') => {
+            *text = text.replace("{name}", name).replace("{id}", id);
+        }
+        Value::Array(items) => items.iter_mut().for_each(|item| substitute(item, name, id)),
+        Value::Object(fields) => fields.values_mut().for_each(|field| substitute(field, name, id)),
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn humanoids() -> Value {
+        json!({
+            "version": 2,
+            "templates": {
+                "humanoid": {
+                    "size": 64,
+                    "offset": { "x": 0, "y": -8 },
+                    "framegroups": [
+                        {
+                            "name": "walk",
+                            "spritesheet": "assets/creatures/{name}/walk.png",
+                            "animations": { "north": { "frame_count": 8, "duration": 100 } }
+                        }
+                    ]
+                },
+                "boss": { "extends": "humanoid", "size": 96, "tags": ["boss"] }
+            },
+            "appearances": [
+                { "id": 1, "name": "orc", "extends": "humanoid", "offset": { "y": -4 } },
+                { "id": 2, "name": "orc_chief", "extends": "boss" },
+                { "id": 3, "name": "rock", "size": 32, "framegroups": [] }
+            ]
+        })
+    }
+
+    #[test]
+    fn test_extends_merges_and_substitutes() {
+        let mut file = humanoids();
+        resolve_templates(&mut file).unwrap();
+
+        let orc = &file["appearances"][0];
+        assert_eq!(orc["size"], 64);
+        assert_eq!(orc["offset"], json!({ "x": 0, "y": -4 }));
+        assert_eq!(orc["framegroups"][0]["spritesheet"], "assets/creatures/orc/walk.png");
+        assert_eq!(orc["extends"], "humanoid");
+
+        let chief = &file["appearances"][1];
+        assert_eq!(chief["size"], 96);
+        assert_eq!(chief["tags"], json!(["boss"]));
+        assert_eq!(
+            chief["framegroups"][0]["spritesheet"],
+            "assets/creatures/orc_chief/walk.png"
+        );
+
+        assert_eq!(file["appearances"][2]["size"], 32);
+    }
+
+    #[test]
+    fn test_unknown_template_and_cycles() {
+        let mut file = json!({
+            "templates": { "a": { "extends": "b" }, "b": { "extends": "a" } },
+            "appearances": [{ "id": 1, "name": "loop", "extends": "a" }]
+        });
+        let error = resolve_templates(&mut file).unwrap_err().to_string();
+        assert!(error.contains("a -> b -> a"), "{}", error);
+
+        let mut file = json!({ "appearances": [{ "id": 1, "name": "orc", "extends": "missing" }] });
+        let error = resolve_templates(&mut file).unwrap_err().to_string();
+        assert!(
+            error.contains("Appearance 'orc' extends unknown template 'missing'"),
+            "{}",
+            error
+        );
+    }
+}
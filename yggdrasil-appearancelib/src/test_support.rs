@@ -0,0 +1,59 @@
+use crate::loaded_types::AppearanceDatabase;
+use crate::types::AppearancesFile;
+use crate::{compile_appearances, load_database_only};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// Pasta temporária de teste, removida no drop (inclusive quando o teste falha)
+pub(crate) struct TempDir {
+    path: PathBuf,
+}
+
+impl TempDir {
+    /// Cria uma pasta vazia e exclusiva (`yggdrasil-<name>-<pid>-<n>`)
+    pub(crate) fn new(name: &str) -> Self {
+        static COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+        let path = std::env::temp_dir().join(format!(
+            "yggdrasil-{}-{}-{}",
+            name,
+            std::process::id(),
+            COUNTER.fetch_add(1, Ordering::Relaxed)
+        ));
+        let _ = fs::remove_dir_all(&path);
+        fs::create_dir_all(&path).unwrap();
+
+        TempDir { path }
+    }
+
+    pub(crate) fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub(crate) fn join<P: AsRef<Path>>(&self, relative: P) -> PathBuf {
+        self.path.join(relative)
+    }
+
+    /// Escreve um arquivo (criando as pastas intermediárias) e retorna o caminho
+    pub(crate) fn write<C: AsRef<[u8]>>(&self, relative: &str, contents: C) -> PathBuf {
+        let path = self.path.join(relative);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(&path, contents).unwrap();
+        path
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.path);
+    }
+}
+
+/// Compila o appearances.json (com os spritesheets já na pasta) e carrega o banco gerado
+pub(crate) fn compile_and_load(root: &TempDir, json: &str) -> AppearanceDatabase {
+    let file: AppearancesFile = serde_json::from_str(json).unwrap();
+    let output = root.join("compiled");
+    compile_appearances(&file, root.path(), output.as_path()).unwrap();
+    load_database_only(&output).unwrap().0
+}
@@ -1,11 +1,16 @@
-use serde::{Deserialize, Deserializer, Serialize};
-use std::collections::HashMap;
+use crate::diagnostic::SourceLocation;
+use schemars::{JsonSchema, Schema, SchemaGenerator, json_schema};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use serde_json::{Map, Value, json};
+use std::collections::{BTreeMap, HashMap};
+use std::path::PathBuf;
 
 // Re-export Direction from common
 pub use yggdrasil_common::types::Direction;
 
 /// Orientação do spritesheet
-#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, JsonSchema, PartialEq, Eq)]
 #[serde(rename_all = "lowercase")]
 pub enum Orientation {
     /// Frames em colunas, direções em linhas (padrão)
@@ -22,6 +27,18 @@ impl Default for Orientation {
     }
 }
 
+/// Geração automática de direções por rotação de uma única linha de frames
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, JsonSchema, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum RotationMode {
+    /// Uma linha de frames (north); as 8 direções são geradas girando em
+    /// passos de 45° com amostragem nearest-neighbour
+    Step45,
+    /// Duas linhas de frames (north e northeast); as demais direções são
+    /// geradas com rotações exatas de 90°
+    Orthogonal,
+}
+
 /// Wrapper para deserializar Option<Direction> com suporte a "null" como string
 fn deserialize_direction_map<'de, D>(deserializer: D) -> Result<HashMap<Option<Direction>, Animation>, D::Error>
 where
@@ -44,62 +61,581 @@ where
     Ok(result)
 }
 
+/// Serializa o mapa de direções usando "null" como chave da animação sem direção
+fn serialize_direction_map<S>(map: &HashMap<Option<Direction>, Animation>, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    let entries: BTreeMap<String, &Animation> = map
+        .iter()
+        .map(|(direction, animation)| (direction_key(*direction), animation))
+        .collect();
+
+    entries.serialize(serializer)
+}
+
+/// Chave de uma direção no mapa de animações (`"null"` = sem direção)
+pub(crate) fn direction_key(direction: Option<Direction>) -> String {
+    let Some(direction) = direction else {
+        return "null".to_string();
+    };
+
+    match serde_json::to_value(direction) {
+        Ok(Value::String(key)) => key,
+        _ => format!("{:?}", direction).to_lowercase(),
+    }
+}
+
+/// Chaves aceitas no mapa de direções (`"null"` = animação sem direção)
+const DIRECTION_KEYS: [&str; 9] = [
+    "null",
+    "north",
+    "east",
+    "south",
+    "west",
+    "northeast",
+    "southeast",
+    "southwest",
+    "northwest",
+];
+
+/// JSON Schema do mapa de direções: um objeto com uma `Animation` por chave de direção
+fn direction_map_schema(generator: &mut SchemaGenerator) -> Schema {
+    let animation = generator.subschema_for::<Animation>();
+    let properties: Map<String, Value> = DIRECTION_KEYS
+        .iter()
+        .map(|key| (key.to_string(), animation.clone().to_value()))
+        .collect();
+
+    json_schema!({
+        "type": "object",
+        "properties": properties,
+        "additionalProperties": false,
+        "minProperties": 1,
+    })
+}
+
 /// Offset para renderização
-#[derive(Debug, Clone, Copy, Serialize, Deserialize, Default)]
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, JsonSchema, Default, PartialEq, Eq)]
 pub struct Offset {
     pub x: i32,
     pub y: i32,
 }
 
+/// Retângulo em coordenadas da sprite (pixels)
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, JsonSchema, Default, PartialEq, Eq)]
+pub struct Rect {
+    pub x:      i32,
+    pub y:      i32,
+    pub width:  u32,
+    pub height: u32,
+}
+
+impl Rect {
+    /// Retorna true se o ponto está dentro do retângulo
+    pub fn contains(&self, x: i32, y: i32) -> bool {
+        // Em i64 para que `x + width` não estoure com offsets ou tamanhos grandes
+        let (x, y) = (x as i64, y as i64);
+        let (left, top) = (self.x as i64, self.y as i64);
+        x >= left && y >= top && x < left + self.width as i64 && y < top + self.height as i64
+    }
+}
+
+/// Tipo de uma forma de colisão
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, JsonSchema, PartialEq, Eq, Hash)]
+#[serde(rename_all = "lowercase")]
+pub enum ShapeKind {
+    /// Área que causa dano (ex.: lâmina da espada durante o ataque)
+    Hitbox,
+    /// Área que recebe dano (corpo da criatura)
+    Hurtbox,
+}
+
+/// Forma de colisão retangular: `{ "kind": "hitbox", "x": 0, "y": 0, "width": 8, "height": 8 }`
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, JsonSchema, PartialEq, Eq)]
+pub struct CollisionShape {
+    pub kind:  ShapeKind,
+    #[serde(flatten)]
+    pub rect:  Rect,
+    /// Frame em que a forma é válida (ausente = todos os frames)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub frame: Option<u32>,
+}
+
+impl CollisionShape {
+    /// Retorna true se a forma é válida no frame
+    pub fn applies_to(&self, frame: usize) -> bool {
+        self.frame.is_none_or(|f| f as usize == frame)
+    }
+}
+
+/// Área ocupada no mapa, em tiles
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, JsonSchema, PartialEq, Eq)]
+pub struct Footprint {
+    pub width:  u32,
+    pub height: u32,
+}
+
+impl Default for Footprint {
+    fn default() -> Self {
+        Footprint { width: 1, height: 1 }
+    }
+}
+
+/// Categoria de uma appearance
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, JsonSchema, PartialEq, Eq, Hash)]
+#[serde(rename_all = "lowercase")]
+pub enum Category {
+    Item,
+    Ground,
+    Creature,
+    Effect,
+    Missile,
+}
+
+/// Propriedades de gameplay de uma appearance
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, JsonSchema, Default, PartialEq, Eq)]
+pub struct Properties {
+    /// Bloqueia a passagem de criaturas
+    #[serde(default)]
+    pub blocking:     bool,
+    /// Pode ser pego pelo jogador
+    #[serde(default)]
+    pub pickupable:   bool,
+    /// Pode ser empilhado no mesmo slot
+    #[serde(default)]
+    pub stackable:    bool,
+    /// Velocidade de movimento sobre o piso (apenas grounds)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ground_speed: Option<u32>,
+}
+
+impl Properties {
+    /// Bits das flags no formato binário do .dat
+    pub(crate) const BLOCKING: u32 = 1 << 0;
+    pub(crate) const PICKUPABLE: u32 = 1 << 1;
+    pub(crate) const STACKABLE: u32 = 1 << 2;
+}
+
+/// Cor RGB, escrita no JSON como string hexadecimal (`"#ff8800"`)
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, JsonSchema, Default, PartialEq, Eq, Hash)]
+#[serde(try_from = "String", into = "String")]
+#[schemars(extend("pattern" = "^#?[0-9a-fA-F]{6}$"))]
+pub struct Color {
+    pub r: u8,
+    pub g: u8,
+    pub b: u8,
+}
+
+impl TryFrom<String> for Color {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        let hex = value.strip_prefix('#').unwrap_or(&value);
+        let channel = |index: usize| hex.get(index..index + 2).and_then(|c| u8::from_str_radix(c, 16).ok());
+
+        match (hex.len(), channel(0), channel(2), channel(4)) {
+            (6, Some(r), Some(g), Some(b)) => Ok(Color { r, g, b }),
+            _ => Err(format!("invalid color `{}`, expected \"#rrggbb\"", value)),
+        }
+    }
+}
+
+impl From<Color> for String {
+    fn from(color: Color) -> Self {
+        format!("#{:02x}{:02x}{:02x}", color.r, color.g, color.b)
+    }
+}
+
+/// Fonte de luz emitida pela appearance (tochas, lava, magias)
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, JsonSchema, PartialEq)]
+pub struct Light {
+    /// Intensidade da luz (1.0 = normal)
+    pub intensity: f32,
+    pub color:     Color,
+    /// Raio de alcance em tiles
+    pub radius:    u32,
+    /// Oscilação da intensidade (opcional)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub flicker:   Option<Flicker>,
+}
+
+impl Light {
+    /// Retorna a intensidade no instante do relógio global, aplicando o flicker
+    pub fn intensity_at(&self, world_time_ms: u64) -> f32 {
+        match self.flicker {
+            Some(flicker) if flicker.period > 0 => {
+                let phase = (world_time_ms % flicker.period as u64) as f32 / flicker.period as f32;
+                self.intensity * (1.0 - flicker.amount * 0.5 * (1.0 - (phase * std::f32::consts::TAU).cos()))
+            }
+            _ => self.intensity,
+        }
+    }
+}
+
+/// Luz de um framegroup: uma luz própria ou `{ "enabled": false }`
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, JsonSchema, PartialEq)]
+#[serde(untagged)]
+pub enum FrameGroupLight {
+    /// Luz deste estado, no lugar da luz da appearance
+    Light(Light),
+    /// Apaga a luz da appearance enquanto o framegroup toca (ex.: tocha apagada)
+    Disabled(DisabledLight),
+}
+
+/// Marcador `{ "enabled": false }` que desliga a luz herdada da appearance
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, JsonSchema, PartialEq, Eq)]
+#[serde(deny_unknown_fields)]
+pub struct DisabledLight {
+    /// Precisa ser `false` (uma luz acesa é definida por completo)
+    pub enabled: bool,
+}
+
+impl FrameGroupLight {
+    /// Luz efetiva do framegroup dada a luz da appearance (None = sem luz)
+    pub fn apply<'a>(framegroup: Option<&'a Self>, appearance: Option<&'a Light>) -> Option<&'a Light> {
+        match framegroup {
+            Some(FrameGroupLight::Light(light)) => Some(light),
+            Some(FrameGroupLight::Disabled(_)) => None,
+            None => appearance,
+        }
+    }
+}
+
+/// Oscilação periódica da intensidade de uma luz
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, JsonSchema, PartialEq)]
+pub struct Flicker {
+    /// Duração de um ciclo em milissegundos
+    pub period: u32,
+    /// Fração da intensidade que oscila (0.0 a 1.0)
+    pub amount: f32,
+}
+
+/// Objeto JSON livre (`"metadata": { ... }`) com dados que o schema não conhece
+///
+/// Ex.: `{ "rarity_tint": "#ffcc00", "editor_folder": "creatures/orcs" }`
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, Default, PartialEq)]
+#[serde(transparent)]
+pub struct Metadata(pub Map<String, Value>);
+
+impl Metadata {
+    /// Retorna true se não há nenhuma chave
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Retorna o valor bruto de uma chave
+    pub fn get(&self, key: &str) -> Option<&Value> {
+        self.0.get(key)
+    }
+
+    /// Retorna uma chave como string (None se ausente ou de outro tipo)
+    pub fn get_str(&self, key: &str) -> Option<&str> {
+        self.get(key)?.as_str()
+    }
+
+    /// Retorna uma chave como inteiro (None se ausente ou de outro tipo)
+    pub fn get_i64(&self, key: &str) -> Option<i64> {
+        self.get(key)?.as_i64()
+    }
+
+    /// Retorna uma chave como número (None se ausente ou de outro tipo)
+    pub fn get_f64(&self, key: &str) -> Option<f64> {
+        self.get(key)?.as_f64()
+    }
+
+    /// Retorna uma chave como booleano (None se ausente ou de outro tipo)
+    pub fn get_bool(&self, key: &str) -> Option<bool> {
+        self.get(key)?.as_bool()
+    }
+
+    /// Deserializa uma chave para um tipo do projeto
+    ///
+    /// Retorna `Ok(None)` se a chave não existe e erro se o valor não é
+    /// compatível com `T`.
+    pub fn get_as<T: DeserializeOwned>(&self, key: &str) -> serde_json::Result<Option<T>> {
+        self.get(key).map(|value| T::deserialize(value)).transpose()
+    }
+
+    /// Retorna todo o objeto como `serde_json::Value`
+    pub fn value(&self) -> Value {
+        Value::Object(self.0.clone())
+    }
+}
+
 /// Arquivo principal de appearances (appearances.json)
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct AppearancesFile {
     pub version:     u32,
+    /// Arquivos ou pastas de fragmentos (mesclados por `parse_appearances_json`)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub include:     Vec<String>,
+    /// Templates reutilizáveis, aplicados às appearances com `extends`
+    /// (resolvidos por `parse_appearances_json`)
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub templates:   HashMap<String, Value>,
     pub appearances: Vec<Appearance>,
 }
 
 /// Uma appearance (criatura, item, efeito, projétil, etc.)
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[schemars(transform = relax_required_with_extends)]
 pub struct Appearance {
-    pub id:          u32,
-    pub name:        String,
+    pub id:            u32,
+    pub name:          String,
+    /// Template de origem (os campos já vêm mesclados após o parse)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub extends:       Option<String>,
+    /// Grupos para busca (ex.: "undead", "boss", "decoration")
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags:          Vec<String>,
     #[serde(default)]
-    pub offset:      Offset,
-    pub size:        u32,
-    pub framegroups: Vec<FrameGroup>,
+    pub offset:        Offset,
+    /// Tamanho da célula quadrada em pixels (atalho para `width` = `height`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub size:          Option<u32>,
+    /// Largura da célula em pixels (sobrescreve `size`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub width:         Option<u32>,
+    /// Altura da célula em pixels (sobrescreve `size`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub height:        Option<u32>,
+    /// Área ocupada no mapa em tiles (padrão: 1×1)
+    #[serde(default)]
+    pub footprint:     Footprint,
+    /// Categoria (item, ground, creature, effect, missile)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub category:      Option<Category>,
+    /// Propriedades de gameplay (blocking, pickupable, stackable, ground_speed)
+    #[serde(default)]
+    pub properties:    Properties,
+    /// Dados livres do projeto, repassados ao .dat sem interpretação
+    #[serde(default, skip_serializing_if = "Metadata::is_empty")]
+    pub metadata:      Metadata,
+    /// Luz emitida (pode ser sobrescrita por framegroup)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub light:         Option<Light>,
+    /// Cor no minimapa (sobrescreve o cálculo automático do compilador)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub minimap_color: Option<Color>,
+    pub framegroups:   Vec<FrameGroup>,
+    /// Posição da appearance no arquivo de origem (preenchida por `parse_appearances`)
+    #[serde(skip)]
+    pub source:        Option<SourceLocation>,
+}
+
+/// No schema, appearances com `extends` só exigem `id` e `name`, já que os
+/// demais campos podem vir do template
+fn relax_required_with_extends(schema: &mut Schema) {
+    if let Some(required) = schema.remove("required") {
+        schema.insert("if".to_string(), json!({ "required": ["extends"] }));
+        schema.insert("then".to_string(), json!({ "required": ["id", "name"] }));
+        schema.insert("else".to_string(), json!({ "required": required }));
+    }
+}
+
+impl Appearance {
+    /// Retorna as dimensões da célula (largura, altura) em pixels
+    ///
+    /// `width` e `height` têm prioridade sobre `size`. Retorna `None` se
+    /// alguma das dimensões não puder ser determinada.
+    pub fn cell_size(&self) -> Option<(u32, u32)> {
+        Some((self.width.or(self.size)?, self.height.or(self.size)?))
+    }
 }
 
 /// Um grupo de frames com diferentes direções e animações
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct FrameGroup {
-    pub name:        String,
+    pub name:          String,
     /// Caminho para o spritesheet que será recortado
-    pub spritesheet: String,
+    /// Opcional se todas as animações informam `sprite_ids`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub spritesheet:   Option<String>,
     /// Orientação do spritesheet (vertical ou horizontal)
     #[serde(default)]
-    pub orientation: Orientation,
+    pub orientation:   Orientation,
+    /// Gera as 8 direções rotacionando o spritesheet (opcional)
+    /// Nesse modo `animations` deve ter uma única entrada, usada como modelo
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rotation:      Option<RotationMode>,
     /// Mapa de direções para animações
     /// Se não houver direções, usar uma única entrada sem direção
-    #[serde(deserialize_with = "deserialize_direction_map")]
-    pub animations:  HashMap<Option<Direction>, Animation>,
+    #[serde(
+        deserialize_with = "deserialize_direction_map",
+        serialize_with = "serialize_direction_map"
+    )]
+    #[schemars(schema_with = "direction_map_schema")]
+    pub animations:    HashMap<Option<Direction>, Animation>,
+    /// Framegroup seguinte quando a animação termina (ex.: "attack" → "idle")
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub next:          Option<String>,
+    /// Se pode ser interrompido antes de terminar (padrão: true)
+    #[serde(default = "default_interruptible")]
+    pub interruptible: bool,
+    /// Framegroup usado quando este não tem animação para a direção pedida
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fallback:      Option<String>,
+    /// Formas de colisão válidas em todas as direções
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub shapes:        Vec<CollisionShape>,
+    /// Dados livres do projeto
+    #[serde(default, skip_serializing_if = "Metadata::is_empty")]
+    pub metadata:      Metadata,
+    /// Luz deste estado (ex.: tocha acesa), sobrescreve a da appearance;
+    /// `{ "enabled": false }` apaga a luz da appearance
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub light:         Option<FrameGroupLight>,
+    /// Arquivo que define o framegroup (o fragmento ou o template), base dos
+    /// caminhos relativos de `spritesheet` (preenchido por `parse_appearances`)
+    #[serde(skip)]
+    pub source_file:   Option<PathBuf>,
+}
+
+fn default_interruptible() -> bool {
+    true
 }
 
 /// Uma animação com lista de sprite IDs
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct Animation {
-    /// Lista de sprite IDs que compõem esta animação
-    /// Estes IDs serão gerados pelo compilador ao recortar o spritesheet
-    #[serde(skip_serializing_if = "Option::is_none")]
-    pub sprite_ids:  Option<Vec<u32>>,
+    /// Sprites já compiladas (.spr em `CompileOptions::sprites_path`) usadas
+    /// no lugar do spritesheet, um ID por frame (ex.: frames compartilhados ou
+    /// importados). Quando informado, `frame_count` é ignorado e os .spr
+    /// precisam existir; eles são ligados ao output sem serem regravados
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sprite_ids:      Option<Vec<u32>>,
     /// Número de frames (usado durante a compilação para recortar)
     #[serde(default = "default_frames")]
-    pub frame_count: u32,
-    /// Duração total da animação em milissegundos
-    #[serde(default)]
-    pub duration:    Option<u32>,
+    pub frame_count:     u32,
+    /// Duração total da animação em milissegundos, dividida igualmente entre
+    /// os frames (ex.: 300 com 3 frames = 100 por frame; ausente ou 0 = estático)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub duration:        Option<u32>,
+    /// Duração de cada frame em milissegundos (alternativa a `duration`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub frame_duration:  Option<u32>,
+    /// Duração individual de cada frame (sobrescreve `duration` e `frame_duration`)
+    /// Deve ter exatamente `frame_count` entradas
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub frame_durations: Option<Vec<FrameDuration>>,
     /// Se a animação deve fazer loop (padrão: true)
-    #[serde(default = "default_looped")]
-    pub looped:      Option<bool>,
+    /// Ignorado quando `playback` é informado
+    #[serde(default = "default_looped", skip_serializing_if = "Option::is_none")]
+    pub looped:          Option<bool>,
+    /// Modo de reprodução (sobrescreve `looped`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub playback:        Option<PlaybackMode>,
+    /// Número de ciclos antes de terminar (`loop` e `ping_pong`, ausente = infinito)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub loop_count:      Option<u32>,
+    /// Frame em que a animação começa (padrão: primeiro)
+    #[serde(default)]
+    pub start_frame:     StartFrame,
+    /// Anima em sincronia com o relógio global (ex.: água e lava no mapa todo)
+    #[serde(default)]
+    pub synchronized:    bool,
+    /// Eventos disparados ao entrar em frames específicos
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub events:          Vec<FrameEvent>,
+    /// Pontos de ancoragem nomeados (ex.: "hand_r", "head", "effect_origin")
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub attachments:     HashMap<String, AttachmentPoint>,
+    /// Formas de colisão desta direção
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub shapes:          Vec<CollisionShape>,
+    /// Dados livres do projeto
+    #[serde(default, skip_serializing_if = "Metadata::is_empty")]
+    pub metadata:        Metadata,
+}
+
+/// Ponto de ancoragem em coordenadas da sprite
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[serde(untagged)]
+pub enum AttachmentPoint {
+    /// Mesmo ponto em todos os frames: `{ "x": 10, "y": 20 }`
+    Fixed(Offset),
+    /// Um ponto por frame: `[{ "x": 10, "y": 20 }, ...]`
+    PerFrame(Vec<Offset>),
+}
+
+/// Evento nomeado disparado ao entrar em um frame (golpe, som, passo...)
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, PartialEq, Eq)]
+pub struct FrameEvent {
+    /// Índice do frame (0 = primeiro)
+    pub frame:   u32,
+    /// Nome do evento (ex.: "hit", "footstep")
+    pub name:    String,
+    /// Dados opcionais do evento (ex.: nome do som)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub payload: Option<String>,
+}
+
+impl Animation {
+    /// Retorna o modo de reprodução efetivo
+    ///
+    /// Sem `playback`, usa `looped`: true = `Loop`, false = `Once`.
+    pub fn playback_mode(&self) -> PlaybackMode {
+        self.playback.unwrap_or(if self.looped.unwrap_or(true) {
+            PlaybackMode::Loop
+        } else {
+            PlaybackMode::Once
+        })
+    }
+}
+
+/// Modo de reprodução de uma animação
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, JsonSchema, PartialEq, Eq, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum PlaybackMode {
+    /// Repete do primeiro ao último frame
+    Loop,
+    /// Toca uma vez e volta ao primeiro frame
+    Once,
+    /// Toca uma vez e mantém o último frame (ex.: porta abrindo)
+    OnceAndHold,
+    /// Vai e volta entre o primeiro e o último frame (1 2 3 2 1 2 3 ...)
+    PingPong,
+}
+
+impl PlaybackMode {
+    /// Retorna true se o modo repete ciclos (`Loop` e `PingPong`)
+    pub fn is_looping(&self) -> bool {
+        matches!(self, PlaybackMode::Loop | PlaybackMode::PingPong)
+    }
+}
+
+/// Política de escolha do frame inicial
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, JsonSchema, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum StartFrame {
+    /// Começa no primeiro frame
+    #[default]
+    First,
+    /// Começa em um frame aleatório (ex.: tochas que não piscam em sincronia)
+    Random,
+    /// Começa em um frame específico: `{ "frame": 2 }`
+    Frame(u32),
+}
+
+/// Duração de um frame: valor fixo ou intervalo aleatório
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, JsonSchema, PartialEq, Eq)]
+#[serde(untagged)]
+pub enum FrameDuration {
+    /// Duração fixa em milissegundos
+    Fixed(u32),
+    /// Duração sorteada entre `min` e `max` (inclusive) a cada exibição do frame
+    Random { min: u32, max: u32 },
+}
+
+impl FrameDuration {
+    /// Retorna o intervalo (min, max) em milissegundos
+    pub fn range(&self) -> (u32, u32) {
+        match *self {
+            FrameDuration::Fixed(duration) => (duration, duration),
+            FrameDuration::Random { min, max } => (min, max),
+        }
+    }
 }
 
 fn default_frames() -> u32 {
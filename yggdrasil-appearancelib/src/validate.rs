@@ -0,0 +1,449 @@
+use crate::check::{self, Problem};
+use crate::compiler::sliced_directions;
+use crate::diagnostic::{Diagnostic, ErrorContext, Severity};
+use crate::resolver::PathResolver;
+use crate::sprite::expected_sheet_size;
+use crate::types::{Animation, Appearance, AppearancesFile, Direction, FrameGroup, RotationMode, direction_key};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+/// Valida todo o arquivo de appearances sem gerar output
+///
+/// Ao contrário de `compile_appearances`, não para no primeiro problema:
+/// retorna todos os erros e avisos, na ordem das appearances. Além das
+/// verificações que a compilação também faz (nomes repetidos, propriedades,
+/// luzes, transições, `rotation`, frames de eventos, anchors e formas,
+/// `start_frame`, `loop_count`, `frame_durations`, tamanhos e footprint
+/// zerados, IDs repetidos, framegroups sem animações e mistura de chaves com
+/// e sem direção), avisa sobre appearances sem framegroups e verifica
+/// spritesheets ausentes e dimensões de spritesheet incompatíveis. A
+/// existência dos .spr de `sprite_ids` só é verificada na compilação, que
+/// conhece a pasta de sprites.
+pub fn validate_appearances<P: AsRef<Path>>(appearances_file: &AppearancesFile, base_path: P) -> Vec<Diagnostic> {
+    validate_appearances_with_resolver(appearances_file, &PathResolver::new(base_path))
+}
+
+/// Valida o arquivo de appearances procurando os spritesheets com `resolver`
+/// (ex.: com raízes de busca adicionais)
+pub fn validate_appearances_with_resolver(
+    appearances_file: &AppearancesFile, resolver: &PathResolver,
+) -> Vec<Diagnostic> {
+    let mut validator = Validator {
+        resolver,
+        sheet_sizes: HashMap::new(),
+        diagnostics: Vec::new(),
+    };
+
+    validator.check_duplicates(appearances_file);
+
+    for (index, appearance) in appearances_file.appearances.iter().enumerate() {
+        validator.check_appearance(Scope {
+            index,
+            appearance,
+            framegroup: None,
+            direction: None,
+        });
+    }
+
+    validator.diagnostics
+}
+
+/// Onde a validação está (appearance, framegroup e direção)
+#[derive(Clone, Copy)]
+struct Scope<'a> {
+    index:      usize,
+    appearance: &'a Appearance,
+    framegroup: Option<(usize, &'a FrameGroup)>,
+    direction:  Option<Option<Direction>>,
+}
+
+impl<'a> Scope<'a> {
+    fn in_framegroup(self, index: usize, framegroup: &'a FrameGroup) -> Self {
+        Self {
+            framegroup: Some((index, framegroup)),
+            ..self
+        }
+    }
+
+    fn in_direction(self, direction: Option<Direction>) -> Self {
+        Self {
+            direction: Some(direction),
+            ..self
+        }
+    }
+
+    fn context(&self, field: &str) -> ErrorContext {
+        let mut context = ErrorContext::default();
+        context.in_field(field);
+        if let Some(direction) = self.direction {
+            context.in_direction(direction);
+        }
+        if let Some((index, framegroup)) = self.framegroup {
+            context.in_framegroup(index, &framegroup.name);
+        }
+        context.in_appearance(self.index, self.appearance);
+        context
+    }
+}
+
+struct Validator<'a> {
+    resolver:    &'a PathResolver,
+    /// Dimensões de cada spritesheet já lido (erro de leitura = None)
+    sheet_sizes: HashMap<PathBuf, Option<(u32, u32)>>,
+    diagnostics: Vec<Diagnostic>,
+}
+
+impl Validator<'_> {
+    fn report(&mut self, severity: Severity, scope: Scope, field: &str, message: String) {
+        self.diagnostics.push(Diagnostic {
+            severity,
+            rule: None,
+            message,
+            context: scope.context(field),
+        });
+    }
+
+    /// Reporta como erros os problemas das verificações compartilhadas com a compilação
+    fn report_problems(&mut self, scope: Scope, problems: Vec<Problem>) {
+        for problem in problems {
+            self.report(Severity::Error, scope, problem.field, problem.message);
+        }
+    }
+
+    fn check_duplicates(&mut self, appearances_file: &AppearancesFile) {
+        let scope = |index: usize| Scope {
+            index,
+            appearance: &appearances_file.appearances[index],
+            framegroup: None,
+            direction: None,
+        };
+
+        for (index, error) in check::duplicate_ids(appearances_file) {
+            self.report(Severity::Error, scope(index), "id", error.to_string());
+        }
+
+        for (index, error) in check::duplicate_names(appearances_file) {
+            self.report(Severity::Error, scope(index), "name", error.to_string());
+        }
+    }
+
+    fn check_appearance(&mut self, scope: Scope) {
+        let appearance = scope.appearance;
+
+        self.report_problems(scope, check::appearance_problems(appearance));
+
+        let cell_size = appearance
+            .cell_size()
+            .filter(|&(width, height)| width > 0 && height > 0);
+
+        if appearance.framegroups.is_empty() {
+            self.report(
+                Severity::Warning,
+                scope,
+                "framegroups",
+                "Appearance has no framegroups".to_string(),
+            );
+        }
+
+        for (index, framegroup) in appearance.framegroups.iter().enumerate() {
+            self.check_framegroup(scope.in_framegroup(index, framegroup), framegroup, cell_size);
+        }
+    }
+
+    fn check_framegroup(&mut self, scope: Scope, framegroup: &FrameGroup, cell_size: Option<(u32, u32)>) {
+        self.report_problems(scope, check::framegroup_problems(scope.appearance, framegroup));
+
+        // Só as animações sem `sprite_ids` são recortadas do spritesheet
+        let sliced = framegroup
+            .animations
+            .values()
+            .filter(|animation| animation.sprite_ids.is_none())
+            .count();
+
+        let sheet = match framegroup.spritesheet.as_deref() {
+            Some(spritesheet) if sliced == 0 && !framegroup.animations.is_empty() => {
+                self.report(
+                    Severity::Warning,
+                    scope,
+                    "spritesheet",
+                    format!(
+                        "Spritesheet '{}' is not used: every animation has `sprite_ids`",
+                        spritesheet
+                    ),
+                );
+                None
+            }
+            Some(spritesheet) => match self.resolver.resolve_for(scope.appearance, framegroup, spritesheet) {
+                Ok(path) => self.sheet_size(scope, &path).map(|size| (path, size)),
+                Err(error) => {
+                    self.report(Severity::Error, scope, "spritesheet", error.to_string());
+                    None
+                }
+            },
+            None => {
+                if sliced > 0 {
+                    self.report(
+                        Severity::Error,
+                        scope,
+                        "spritesheet",
+                        "Framegroup needs a `spritesheet` for animations without `sprite_ids`".to_string(),
+                    );
+                }
+                None
+            }
+        };
+
+        // Framegroup vazio já reportado por `framegroup_problems`
+        if framegroup.animations.is_empty() {
+            return;
+        }
+
+        // Com chaves misturadas o layout do spritesheet é ambíguo, então as dimensões não são verificadas
+        let mixed = check::is_mixed(framegroup);
+
+        // Ordem estável das direções nas mensagens
+        let mut animations: Vec<(&Option<Direction>, &Animation)> = framegroup.animations.iter().collect();
+        animations.sort_by_key(|(direction, _)| direction_key(**direction));
+
+        for (direction, animation) in &animations {
+            self.report_problems(scope.in_direction(**direction), check::animation_problems(animation));
+        }
+
+        let Some((path, (sheet_width, sheet_height))) = sheet else {
+            return;
+        };
+        let Some((cell_width, cell_height)) = cell_size.filter(|_| !mixed) else {
+            return;
+        };
+
+        // Layout esperado (frames, direções) de cada animação
+        let layouts: Vec<(u32, u32)> = match framegroup.rotation {
+            Some(mode) => {
+                // Já reportado por `framegroup_problems`
+                if animations.len() != 1 {
+                    return;
+                }
+                if cell_width != cell_height {
+                    self.report(
+                        Severity::Error,
+                        scope,
+                        "rotation",
+                        format!("Rotation requires square cells, got {}x{}", cell_width, cell_height),
+                    );
+                    return;
+                }
+
+                let rows = match mode {
+                    RotationMode::Step45 => 0,
+                    RotationMode::Orthogonal => 2,
+                };
+                vec![(animations[0].1.frame_count, rows)]
+            }
+            None => animations
+                .iter()
+                .filter(|(_, animation)| animation.sprite_ids.is_none())
+                .map(|(direction, animation)| {
+                    let directions = if direction.is_some() {
+                        sliced_directions(&framegroup.animations).len() as u32
+                    } else {
+                        0
+                    };
+                    (animation.frame_count, directions)
+                })
+                .collect(),
+        };
+
+        // Uma mensagem por tamanho esperado diferente
+        let mut reported = Vec::new();
+        for (frames, directions) in layouts {
+            let (width, height) =
+                expected_sheet_size(cell_width, cell_height, frames, directions, framegroup.orientation);
+            if (width, height) == (sheet_width, sheet_height) || reported.contains(&(width, height)) {
+                continue;
+            }
+            reported.push((width, height));
+
+            self.report(
+                Severity::Error,
+                scope,
+                "spritesheet",
+                format!(
+                    "Spritesheet '{}' is {}x{}, expected {}x{} ({} frame(s), {} direction(s), {} orientation)",
+                    path.display(),
+                    sheet_width,
+                    sheet_height,
+                    width,
+                    height,
+                    frames,
+                    directions.max(1),
+                    format!("{:?}", framegroup.orientation).to_lowercase()
+                ),
+            );
+        }
+    }
+
+    /// Lê as dimensões do spritesheet (já resolvido), reportando arquivos ilegíveis
+    fn sheet_size(&mut self, scope: Scope, path: &Path) -> Option<(u32, u32)> {
+        if let Some(size) = self.sheet_sizes.get(path) {
+            return *size;
+        }
+
+        let size = match image::image_dimensions(path) {
+            Ok(size) => Some(size),
+            Err(error) => {
+                self.report(
+                    Severity::Error,
+                    scope,
+                    "spritesheet",
+                    format!("Could not read spritesheet '{}': {}", path.display(), error),
+                );
+                None
+            }
+        };
+
+        self.sheet_sizes.insert(path.to_path_buf(), size);
+        size
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::TempDir;
+
+    #[test]
+    fn test_reports_every_problem_at_once() {
+        let root = TempDir::new("validate");
+        image::RgbaImage::new(64, 32).save(root.join("walk.png")).unwrap();
+
+        let file: AppearancesFile = serde_json::from_value(serde_json::json!({
+            "version": 2,
+            "appearances": [
+                {
+                    "id": 1,
+                    "name": "orc",
+                    "size": 32,
+                    "framegroups": [
+                        {
+                            "name": "walk",
+                            "spritesheet": root.join("walk.png"),
+                            "animations": {
+                                "north": { "frame_count": 2 },
+                                "south": { "frame_count": 2 }
+                            }
+                        },
+                        {
+                            "name": "idle",
+                            "spritesheet": root.join("missing.png"),
+                            "animations": {
+                                "null": { "frame_count": 1 },
+                                "north": { "frame_count": 1 }
+                            }
+                        },
+                        { "name": "death", "spritesheet": root.join("walk.png"), "animations": {} }
+                    ]
+                },
+                { "id": 1, "name": "rock", "size": 0, "framegroups": [] }
+            ]
+        }))
+        .unwrap();
+
+        let diagnostics = validate_appearances(&file, root.path());
+        let messages: Vec<String> = diagnostics.iter().map(|d| d.message.clone()).collect();
+        let has = |text: &str| messages.iter().any(|message| message.contains(text));
+
+        assert!(has("Duplicate appearance ID 1"), "{:#?}", messages);
+        assert!(has("is 64x32, expected 64x64"), "{:#?}", messages);
+        assert!(has("missing.png' not found"), "{:#?}", messages);
+        assert!(has("mixes directional animations"), "{:#?}", messages);
+        assert!(has("Framegroup has no animations"), "{:#?}", messages);
+        assert!(has("`size` must be greater than zero"), "{:#?}", messages);
+
+        let warning = diagnostics.iter().find(|d| d.severity == Severity::Warning).unwrap();
+        assert_eq!(warning.context.appearance_name.as_deref(), Some("rock"));
+
+        let mismatch = diagnostics.iter().find(|d| d.message.contains("64x32")).unwrap();
+        assert_eq!(mismatch.context.framegroup.as_deref(), Some("walk"));
+        assert_eq!(
+            mismatch.context.path.as_deref(),
+            Some("appearances[0].framegroups[0].spritesheet")
+        );
+    }
+
+    #[test]
+    fn test_reports_what_compile_rejects() {
+        let root = TempDir::new("validate-compile");
+        let file: AppearancesFile = serde_json::from_value(serde_json::json!({
+            "version": 2,
+            "appearances": [
+                {
+                    "id": 1,
+                    "name": "grass",
+                    "size": 32,
+                    "category": "item",
+                    "properties": { "ground_speed": 150 },
+                    "framegroups": [
+                        {
+                            "name": "walk",
+                            "animations": {
+                                "null": {
+                                    "sprite_ids": [1, 2],
+                                    "start_frame": { "frame": 9 },
+                                    "events": [{ "frame": 7, "name": "step" }],
+                                    "frame_durations": [100]
+                                }
+                            }
+                        },
+                        {
+                            "name": "walk",
+                            "shapes": [{ "kind": "hitbox", "x": 0, "y": 0, "width": 4, "height": 4, "frame": 3 }],
+                            "animations": { "null": { "sprite_ids": [1] } }
+                        }
+                    ]
+                },
+                {
+                    "id": 2,
+                    "name": "empty",
+                    "size": 0,
+                    "footprint": { "width": 0, "height": 1 },
+                    "framegroups": [{ "name": "idle", "animations": { "null": { "sprite_ids": [1] } } }]
+                }
+            ]
+        }))
+        .unwrap();
+
+        let diagnostics = validate_appearances(&file, root.path());
+        let paths: Vec<&str> = diagnostics.iter().filter_map(|d| d.context.path.as_deref()).collect();
+        assert_eq!(
+            paths,
+            [
+                "appearances[0].properties",
+                "appearances[0].framegroups",
+                "appearances[0].framegroups[0].animations.null.start_frame",
+                "appearances[0].framegroups[0].animations.null.events",
+                "appearances[0].framegroups[0].animations.null.frame_durations",
+                "appearances[0].framegroups[1].shapes",
+                "appearances[1].size",
+                "appearances[1].footprint",
+            ]
+        );
+
+        // A compilação rejeita o mesmo arquivo com a mesma mensagem
+        let compile_error = |file: &AppearancesFile| {
+            crate::compile_appearances(file, root.path(), &root.join("compiled"))
+                .err()
+                .unwrap()
+                .root()
+                .to_string()
+        };
+        let expected = |index: usize| format!("Invalid appearance data: {}", diagnostics[index].message);
+        assert_eq!(compile_error(&file), expected(0));
+
+        // Inclusive tamanho e footprint zerados, mesmo com `sprite_ids`
+        let mut empty = file.clone();
+        empty.appearances.remove(0);
+        assert_eq!(compile_error(&empty), expected(6));
+        empty.appearances[0].size = Some(32);
+        assert_eq!(compile_error(&empty), expected(7));
+    }
+}
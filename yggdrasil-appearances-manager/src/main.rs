@@ -1,13 +1,22 @@
-use anyhow::Result;
-use clap::Parser;
-use std::path::PathBuf;
-use yggdrasil_appearancelib::{compile_appearances, parse_appearances_json};
+use anyhow::{Result, bail};
+use clap::{Parser, Subcommand};
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+use yggdrasil_appearancelib::{
+    AppearanceError, CompileOptions, Diagnostic, ErrorContext, LintConfig, MinimapColorMode, PathResolver, Severity,
+    compile_appearances_with_options, convert_file, lint_appearances, parse_appearances,
+    parse_appearances_for_validation, validate_appearances_with_resolver, write_schema,
+};
 
 #[derive(Parser, Debug)]
 #[command(name = "yggdrasil-appearances-manager")]
 #[command(author, version, about = "Compile appearances.json into binary format", long_about = None)]
 struct Args {
-    /// Path to appearances.json file
+    /// Subcommand to run (compiles when omitted)
+    #[command(subcommand)]
+    command: Option<Command>,
+
+    /// Path to appearances file (.json, .toml, .yaml, .yml or .ron)
     #[arg(short, long, default_value = "assets/appearances/appearances.json")]
     input: PathBuf,
 
@@ -18,26 +27,212 @@ struct Args {
     /// Base path for resolving sprite paths (usually project root)
     #[arg(short, long, default_value = ".")]
     base_path: PathBuf,
+
+    /// Extra directory to search for spritesheets (repeatable, supports ~ and $VARS)
+    #[arg(short = 'r', long = "asset-root")]
+    asset_roots: Vec<PathBuf>,
+
+    /// Directory with the pre-built .spr files referenced by sprite_ids (linked into the output) [default: <output>]
+    #[arg(long)]
+    sprites: Option<PathBuf>,
+
+    /// Generate masks.dat with 1-bit alpha masks for pixel-perfect hit testing
+    #[arg(long)]
+    alpha_masks: bool,
+
+    /// Alpha value a pixel must exceed to be considered opaque in the masks
+    #[arg(long, default_value_t = 0)]
+    alpha_threshold: u8,
+
+    /// Compute minimap colors from each appearance's first frame (off, average, dominant)
+    #[arg(long, default_value = "off")]
+    minimap_color: MinimapColorMode,
 }
 
-fn main() -> Result<()> {
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Convert an appearances file between JSON, TOML, YAML and RON (by extension)
+    Convert {
+        /// Source file
+        input:  PathBuf,
+        /// Destination file
+        output: PathBuf,
+    },
+    /// Write the JSON Schema of appearances.json (for editor validation and completion)
+    Schema {
+        /// Destination file
+        #[arg(default_value = "assets/appearances/appearances.schema.json")]
+        output: PathBuf,
+    },
+    /// Check an appearances file and report every problem without writing output
+    Validate {
+        /// Path to appearances file
+        #[arg(default_value = "assets/appearances/appearances.json")]
+        input:       PathBuf,
+        /// Base path for resolving sprite paths (usually project root)
+        #[arg(short, long, default_value = ".")]
+        base_path:   PathBuf,
+        /// Extra directory to search for spritesheets (repeatable, supports ~ and $VARS)
+        #[arg(short = 'r', long = "asset-root")]
+        asset_roots: Vec<PathBuf>,
+    },
+    /// Check an appearances file against the project's lint rules (fails on errors)
+    Lint {
+        /// Path to appearances file
+        #[arg(default_value = "assets/appearances/appearances.json")]
+        input:  PathBuf,
+        /// Lint rules file (.json, .toml, .yaml, .yml or .ron), e.g. the shipped lint.toml
+        #[arg(short, long)]
+        config: PathBuf,
+    },
+}
+
+fn main() -> ExitCode {
     let args = Args::parse();
 
+    let result = match &args.command {
+        Some(Command::Convert { input, output }) => convert(input, output),
+        Some(Command::Schema { output }) => schema(output),
+        Some(Command::Validate {
+            input,
+            base_path,
+            asset_roots,
+        }) => validate(input, base_path, asset_roots),
+        Some(Command::Lint { input, config }) => lint(input, config),
+        None => compile(&args),
+    };
+
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(error) => {
+            report(&error);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+/// Mostra o erro com o trecho do arquivo de appearances onde ele aconteceu
+fn report(error: &anyhow::Error) {
+    let Some(error) = error.downcast_ref::<AppearanceError>() else {
+        eprintln!("❌ Error: {:#}", error);
+        return;
+    };
+
+    eprintln!("❌ Error: {}", error.root());
+
+    if let Some(context) = error.context() {
+        print_context(context);
+    }
+}
+
+/// Mostra onde um erro ou diagnóstico aconteceu, com o trecho do arquivo
+fn print_context(context: &ErrorContext) {
+    eprintln!("   in {}", context);
+
+    if let Some(snippet) = context.location.as_ref().and_then(|location| location.snippet()) {
+        eprintln!();
+        eprintln!("{}", snippet);
+    }
+}
+
+/// Mostra os diagnósticos e falha se algum for um erro
+fn print_diagnostics(diagnostics: &[Diagnostic]) -> Result<()> {
+    for diagnostic in diagnostics {
+        let rule = diagnostic.rule.map(|rule| format!("[{}]", rule)).unwrap_or_default();
+        match diagnostic.severity {
+            Severity::Error => eprintln!("❌ error{}: {}", rule, diagnostic.message),
+            Severity::Warning => eprintln!("⚠️  warning{}: {}", rule, diagnostic.message),
+        }
+        print_context(&diagnostic.context);
+        eprintln!();
+    }
+
+    let errors = diagnostics
+        .iter()
+        .filter(|diagnostic| diagnostic.severity == Severity::Error)
+        .count();
+    let warnings = diagnostics.len() - errors;
+
+    if errors > 0 {
+        bail!("{} error(s), {} warning(s)", errors, warnings);
+    }
+
+    if warnings > 0 {
+        println!("⚠️  {} warning(s)", warnings);
+    } else {
+        println!("✅ No problems found");
+    }
+    Ok(())
+}
+
+fn convert(input: &Path, output: &Path) -> Result<()> {
+    convert_file(input, output)?;
+    println!("✅ Converted {} → {}", input.display(), output.display());
+    Ok(())
+}
+
+fn schema(output: &Path) -> Result<()> {
+    write_schema(output)?;
+    println!("✅ Schema written to {}", output.display());
+    Ok(())
+}
+
+fn validate(input: &Path, base_path: &Path, asset_roots: &[PathBuf]) -> Result<()> {
+    let resolver = PathResolver::new(base_path).with_roots(asset_roots)?;
+    let appearances = parse_appearances_for_validation(input)?;
+    println!(
+        "🔍 Validating {} appearances from {}",
+        appearances.appearances.len(),
+        input.display()
+    );
+
+    print_diagnostics(&validate_appearances_with_resolver(&appearances, &resolver))
+}
+
+fn lint(input: &Path, config: &Path) -> Result<()> {
+    let config = LintConfig::from_file(config)?;
+
+    let appearances = parse_appearances(input)?;
+    println!(
+        "🔍 Linting {} appearances from {} ({} rules)",
+        appearances.appearances.len(),
+        input.display(),
+        config.rules.len()
+    );
+
+    print_diagnostics(&lint_appearances(&appearances, &config))
+}
+
+fn compile(args: &Args) -> Result<()> {
     println!("🎮 Yggdrasil Appearances Manager");
     println!("━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━");
     println!("📄 Input:  {}", args.input.display());
     println!("📂 Output: {}", args.output.display());
     println!("🗂️  Base:   {}", args.base_path.display());
+    for root in &args.asset_roots {
+        println!("📁 Root:   {}", root.display());
+    }
+    if let Some(sprites) = &args.sprites {
+        println!("🧩 Sprites: {}", sprites.display());
+    }
     println!();
 
-    // Parse appearances.json
-    print!("📖 Parsing appearances.json... ");
-    let appearances = parse_appearances_json(&args.input)?;
+    // Parse appearances (um erro termina a linha de progresso antes de ser reportado)
+    print!("📖 Parsing {}... ", args.input.display());
+    let appearances = parse_appearances(&args.input).inspect_err(|_| println!())?;
     println!("✓ {} appearances found", appearances.appearances.len());
 
     // Compile
     print!("🔨 Compiling sprites... ");
-    let result = compile_appearances(&appearances, &args.base_path, &args.output)?;
+    let options = CompileOptions {
+        alpha_masks:     args.alpha_masks,
+        alpha_threshold: args.alpha_threshold,
+        minimap_color:   args.minimap_color,
+        asset_roots:     args.asset_roots.clone(),
+        sprites_path:    args.sprites.clone(),
+    };
+    let result = compile_appearances_with_options(&appearances, &args.base_path, &args.output, &options)
+        .inspect_err(|_| println!())?;
     println!("✓");
 
     // Summary
@@ -47,6 +242,9 @@ fn main() -> Result<()> {
     println!("📊 Summary:");
     println!("   • Appearances: {}", result.appearances_count);
     println!("   • Unique sprites: {}", result.sprites_count);
+    if result.reused_sprites_count > 0 {
+        println!("   • Reused sprites: {}", result.reused_sprites_count);
+    }
     println!(
         "   • appearances.dat: {} bytes ({:.2} KB)",
         result.dat_size,
@@ -60,8 +258,12 @@ fn main() -> Result<()> {
     println!();
     println!("📁 Output files:");
     println!("   • {}/appearances.dat", args.output.display());
+    if args.alpha_masks {
+        println!("   • {}/masks.dat", args.output.display());
+    }
+    // IDs reservados por `sprite_ids` deixam lacunas, então não há um intervalo contíguo
     println!(
-        "   • {}/00001.spr ... {:05}.spr",
+        "   • {}/*.spr ({} new sprites)",
         args.output.display(),
         result.sprites_count
     );
@@ -0,0 +1,100 @@
+use std::fs;
+use std::path::Path;
+use std::process::{Command, Output};
+
+/// Arquivo versionado na pasta do manager (`lint.toml`, `example_appearances.json`)
+fn shipped(name: &str) -> String {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join(name).display().to_string()
+}
+
+fn run(args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_yggdrasil-appearances-manager"))
+        .args(args)
+        .output()
+        .unwrap()
+}
+
+#[test]
+fn test_validate_reports_duplicate_ids_with_other_problems() {
+    let root = tempfile::tempdir().unwrap();
+    let input = root.path().join("appearances.json");
+    fs::write(
+        &input,
+        r#"{
+            "version": 2,
+            "appearances": [
+                { "id": 1, "name": "rock", "size": 32, "framegroups": [] },
+                { "id": 1, "name": "tree", "size": 0, "framegroups": [] }
+            ]
+        }"#,
+    )
+    .unwrap();
+
+    let output = run(&["validate", input.to_str().unwrap(), "-b", root.path().to_str().unwrap()]);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+
+    assert!(!output.status.success());
+    assert!(
+        stderr.contains("Duplicate appearance ID 1 in 'rock' and 'tree'"),
+        "{}",
+        stderr
+    );
+    assert!(stderr.contains("`size` must be greater than zero"), "{}", stderr);
+    assert!(stderr.contains("2 error(s), 2 warning(s)"), "{}", stderr);
+}
+
+#[test]
+fn test_lint_fails_on_rule_errors() {
+    let root = tempfile::tempdir().unwrap();
+    let input = root.path().join("appearances.json");
+    fs::write(
+        &input,
+        r#"{
+            "version": 2,
+            "appearances": [
+                { "id": 1, "name": "banner", "width": 32, "height": 48, "framegroups": [] }
+            ]
+        }"#,
+    )
+    .unwrap();
+
+    // As regras da casa só aceitam 32 ou 64
+    let output = run(&["lint", input.to_str().unwrap(), "-c", &shipped("lint.toml")]);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+
+    assert!(!output.status.success());
+    assert!(stderr.contains("error[allowed_sizes]: Size 32x48 is not allowed"), "{}", stderr);
+    assert!(stderr.contains("appearances[0].height"), "{}", stderr);
+    assert!(stderr.contains("1 error(s), 0 warning(s)"), "{}", stderr);
+
+    // Com uma configuração que rebaixa a regra para aviso, o lint passa
+    let config = root.path().join("lint.toml");
+    fs::write(
+        &config,
+        "[[rules]]\nrule = \"allowed_sizes\"\nseverity = \"warning\"\nsizes = [32, 64]\n",
+    )
+    .unwrap();
+
+    let output = run(&["lint", input.to_str().unwrap(), "-c", config.to_str().unwrap()]);
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+}
+
+#[test]
+fn test_lint_requires_a_config() {
+    let output = run(&["lint", &shipped("example_appearances.json")]);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+
+    assert!(!output.status.success());
+    assert!(stderr.contains("--config <CONFIG>"), "{}", stderr);
+}
+
+#[test]
+fn test_example_only_breaks_the_size_rule() {
+    // O exemplo mostra efeitos e mísseis fora dos tamanhos da casa (48, 96 e 24)
+    let output = run(&["lint", &shipped("example_appearances.json"), "-c", &shipped("lint.toml")]);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+
+    assert!(!output.status.success());
+    assert_eq!(stderr.matches("error[allowed_sizes]").count(), 3, "{}", stderr);
+    assert!(stderr.contains("3 error(s), 0 warning(s)"), "{}", stderr);
+}